@@ -6,41 +6,112 @@ mod messagebar;
 mod uicomponent;
 mod command;
 mod commandbar;
+mod pathcompleter;
+mod lineending;
+mod namedcommand;
+mod encoding;
+mod settings;
+mod diff;
+mod picker;
+mod layout;
+mod tabbar;
+mod filetype;
+mod indentation;
+mod fuzzy;
+mod filefinder;
+mod projectsearch;
 mod line;
 mod position;
 mod size;
+#[cfg(test)]
+mod testdir;
 
 use std::f32::DIGITS;
 use std::io::Error;
 use std::panic::{set_hook,take_hook};
+use std::path::{Path,PathBuf};
+use std::fs;
+use std::io::ErrorKind;
 use std::{env};
+use std::time::{Duration,Instant};
+use std::cell::RefCell;
+use std::rc::Rc;
 use crossterm::event::{
     Event,
-    KeyEvent, KeyEventKind, read,
+    KeyEvent, KeyEventKind, read, poll,
+    MouseButton, MouseEvent, MouseEventKind,
 };
 use terminal::{Terminal};
-use view::View;
+use view::{Buffer,BufferOptions,DiskChange,View};
 
 use uicomponent::UIComponent;
 use statusbar::StatusBar;
 use commandbar::CommandBar;
+use picker::Picker;
+use layout::{Direction,Layout,Rect};
+use tabbar::{Tab,TabBar};
+use filefinder::FileFinder;
+use indentation::Indentation;
+use filetype::FileType;
+use projectsearch::ProjectSearch;
 
 use crate::editor::position::Position;
 
 //self 表示当前的模块，editor;要使用下面的子模块，通常可以省略，为了路径清晰，可以添加上
 use self::{
     command::{
-        Command::{self,Edit,Move,System}, //use 简化路径，这里可以直接使用Command::Edit，Command::Move,Command::System，这几个变体
-        System::{Quit,Resize,Save,Dismiss} //use 简化路径，这里可以直接使用 System 的几个变体
+        Command::{self,Edit,Move,Select,System}, //use 简化路径，这里可以直接使用Command::Edit，Command::Move,Command::System，这几个变体
+        System::{Quit,Resize,Save,Dismiss,Execute,NextBuffer,PreviousBuffer,ListBuffers,MoveFocus,SelectBuffer,Click,FindFile,Open} //use 简化路径，这里可以直接使用 System 的几个变体
     },
     messagebar::Messagebar,
+    namedcommand::NamedCommand,
+    settings::Settings,
 };
 use size::Size;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");//版本号
 const NAME: &str = env!("CARGO_PKG_NAME");//文件名
 const QUIT_TIMES: u8 = 3; //退出确认次数
+const TICK_INTERVAL: Duration = Duration::from_millis(500); //没有输入时，定时检查交换文件等的间隔
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(2); //定时检查文件是否被其他程序修改的间隔
+const BACKGROUND_TICK_INTERVAL: Duration = Duration::from_millis(50); //查找文件或搜索在后台进行时，更频繁地刷新结果
 
+///提示栏当前的用途
+#[derive(Default)]
+enum PromptType {
+    #[default]
+    Save, //输入文件名保存
+    Confirm(Confirmation), //等待用户键入选项，如 y/n
+    Command, //输入命名指令，如 eol crlf
+    CreateFile, //在目录列表中新建文件，以 / 结尾时新建目录
+    Rename(PathBuf), //在目录列表中重命名条目
+    FindFile, //输入查找文件的模糊匹配内容
+    Open, //输入要打开的文件
+}
+
+///需要用户确认之后才执行的操作
+enum Confirmation {
+    Overwrite(PathBuf), //覆盖一个已经存在的其他文件
+    CreateDirectory(PathBuf), //保存前创建不存在的父目录
+    RecoverSwap(PathBuf), //打开文件时发现上一次异常退出留下的交换文件
+    ReloadChanged, //文件被其他程序修改，当前没有未保存的修改
+    ResolveConflict, //文件被其他程序修改，当前也有未保存的修改
+    CloseBuffer(usize), //关闭一个有未保存修改的buffer
+    DeleteEntry(PathBuf), //在目录列表中删除文件或空目录
+    RenameEntry(PathBuf, PathBuf), //重命名时覆盖一个已经存在的文件
+    Revert, //放弃未保存的修改，重新读取文件
+}
+
+impl Confirmation {
+    ///可以键入的选项
+    const fn choices(&self) -> &'static [char] {
+        match self {
+            Self::RecoverSwap(_) => &['r', 'd', 'v'],
+            Self::ResolveConflict => &['o', 'r', 'd'],
+            _ => &['y', 'n'],
+        }
+    }
+}
 
 
 pub struct Editor {
@@ -49,9 +120,21 @@ pub struct Editor {
     status_bar: StatusBar,
     message_bar: Messagebar,
     command_bar: Option<CommandBar>, //用于保存文件时候的指令显示和信息输入,但是不是常常出现，所以这里是Option
+    prompt_type: PromptType, //command_bar 存在时，表示其用途
     terminal_size: Size, 
     title: String,
     quit_times: u8,
+    settings: Settings,
+    overlay: Option<View>, //只读的临时内容(如比较结果)，存在时显示在view的位置，Esc关闭
+    last_swap_update: Instant, //上一次检查交换文件的时间
+    last_input: Instant, //上一次输入的时间，用于空闲时自动保存
+    last_disk_check: Instant, //上一次检查文件是否被其他程序修改的时间
+    buffers: Vec<Rc<RefCell<Buffer>>>, //所有打开的buffer，view显示其中之一
+    picker: Option<Picker>, //buffer列表，存在时显示在view的位置
+    layout: Layout, //分屏的布局，view是其中获得焦点的窗口
+    tab_bar: TabBar, //最上面一行，列出打开的buffer
+    finder: Option<FileFinder>, //查找文件的列表和预览，存在时显示在view的位置
+    search: Option<(ProjectSearch, Rc<RefCell<Buffer>>)>, //正在后台进行的搜索，以及显示其结果的buffer
 }
 
 
@@ -73,15 +156,46 @@ impl Editor {
         editor.resize(size); 
 
         //更新message.bar的文字信息
-        editor.message_bar.update_message("HELP: Ctrl-s = save | Ctrl-c = quit"); 
+        editor.message_bar.update_message("HELP: Ctrl-s = save | Ctrl-c = quit | Ctrl-e = command"); 
 
-        //更新view
+        //读取配置文件，配置有误时提示第一条错误
+        let (settings, errors) = Settings::load();
+        editor.settings = settings;
+        if let Some(err) = errors.first() {
+            editor.message_bar.update_message(&format!("Config: {err}"));
+        }
+        if editor.settings.mouse {
+            Terminal::set_mouse_capture(true)?;
+        }
+
+        //打开命令行中的每一个文件，显示第一个；--readonly(-R) 以只读模式打开
         let args: Vec<String> = env::args().collect();
-        if let Some(file_name) = args.get(1) {
-            if editor.view.load(&file_name).is_err() {
-                editor.message_bar.update_message(&format!("ERR: Could not open file: {file_name}"));
+        let read_only = args.iter().skip(1).any(|arg| arg == "--readonly" || arg == "-R");
+        let mut failed = Vec::new();
+        for file_name in args.iter().skip(1).filter(|arg| *arg != "--readonly" && *arg != "-R") {
+            if editor.load_buffer(file_name, read_only).is_err() {
+                failed.push(file_name);
             }
         }
+        if editor.buffers.is_empty() {
+            let buffer = editor.empty_buffer();
+            editor.view.set_buffer(Rc::clone(&buffer));
+            editor.buffers.push(buffer);
+        }
+        editor.switch_to_buffer(0);
+        //列出所有打开失败的文件，而不只是第一个
+        match failed.as_slice() {
+            [] => {},
+            [file_name] => editor.message_bar.update_message(&format!("ERR: Could not open file: {file_name}")),
+            file_names => {
+                let names: Vec<&str> = file_names.iter().map(|name| name.as_str()).collect();
+                editor.message_bar.update_message(&format!(
+                    "ERR: Could not open {} files: {}",
+                    names.len(),
+                    names.join(", ")
+                ));
+            },
+        }
 
         
         //更新状态栏的文字信息
@@ -93,9 +207,21 @@ impl Editor {
     ///更新editor的terminal_size 以及 成员中需要的terminal_size
     fn resize(&mut self, size: Size) {
         self.terminal_size = size;
-        self.view.resize( Size { 
+        self.layout_windows();
+        let area = self.text_area();
+        if let Some(overlay) = &mut self.overlay {
+            overlay.set_area(area.origin, area.size);
+        }
+        if let Some(picker) = &mut self.picker {
+            picker.resize(area.size);
+        }
+        if let Some(finder) = &mut self.finder {
+            finder.resize(area.size);
+        }
+
+        self.tab_bar.resize( Size {
             columns: size.columns,
-            rows: size.rows.saturating_sub(2) //预留两行空间
+            rows: 1,
         });
 
         self.message_bar.resize( Size {
@@ -116,6 +242,139 @@ impl Editor {
         } 
     }
 
+    ///显示文本的区域，预留第一行给标签栏，最后两行给状态栏和信息栏
+    const fn text_area(&self) -> Rect {
+        Rect {
+            origin: Position { column: 0, row: 1 },
+            size: Size {
+                columns: self.terminal_size.columns,
+                rows: self.terminal_size.rows.saturating_sub(3),
+            },
+        }
+    }
+
+    ///按照布局为每个窗口分配区域；有多个窗口时，每个窗口最后一行显示文件名
+    fn layout_windows(&mut self) {
+        let rects = self.layout.rects(self.text_area());
+        let focused = self.layout.focused_index();
+        let title_rows = usize::from(rects.len() > 1);
+        let view_size = |rect: &Rect| Size {
+            columns: rect.size.columns,
+            rows: rect.size.rows.saturating_sub(title_rows),
+        };
+
+        if let Some(rect) = rects.get(focused) {
+            self.view.set_area(rect.origin, view_size(rect));
+        }
+        let other_rects = rects.iter().enumerate().filter(|(index, _)| *index != focused);
+        for (view, (_, rect)) in self.layout.views_mut().into_iter().zip(other_rects) {
+            view.set_area(rect.origin, view_size(rect));
+        }
+    }
+
+    ///所有窗口都需要重新渲染，如关闭了覆盖在上面的临时内容
+    fn redraw_windows(&mut self) {
+        self.view.set_needs_redraw(true);
+        for view in self.layout.views_mut() {
+            view.set_needs_redraw(true);
+        }
+    }
+
+    ///渲染所有窗口，以及多个窗口时的文件名和分隔线
+    fn render_windows(&mut self) {
+        for view in self.layout.views_mut() {
+            view.render(view.origin().row);
+        }
+        self.view.render(self.view.origin().row);
+
+        if self.layout.window_count() < 2 {
+            return;
+        }
+        let area = self.text_area();
+        let rects = self.layout.rects(area);
+        for (rect, window) in rects.iter().zip(self.layout.windows()) {
+            let is_focused = window.is_none();
+            let status = window.unwrap_or(&self.view).get_status();
+            let marker = if is_focused { '*' } else { ' ' };
+            let title: String = format!("{marker} {} {}", status.file_name, status.modified_indicator_to_string())
+                .chars()
+                .take(rect.size.columns)
+                .collect();
+            let columns = rect.size.columns;
+            let position = Position { column: rect.origin.column, row: rect.origin.row.saturating_add(rect.size.rows).saturating_sub(1) };
+            let _ = Terminal::print_inverted_at(position, &format!("{title:<columns$}"));
+        }
+        for separator in self.layout.separators(area) {
+            for row in separator.origin.row..separator.origin.row.saturating_add(separator.size.rows) {
+                let _ = Terminal::print_at(Position { column: separator.origin.column, row }, "|");
+            }
+        }
+    }
+
+    ///将当前窗口一分为二，两个窗口显示同一个buffer
+    fn split_window(&mut self, direction: Direction) {
+        let rects = self.layout.rects(self.text_area());
+        let too_small = rects.get(self.layout.focused_index()).is_none_or(|rect| match direction {
+            Direction::Horizontal => rect.size.rows < 4,
+            Direction::Vertical => rect.size.columns < 8,
+        });
+        if too_small {
+            self.message_bar.update_message("Not enough room to split");
+            return;
+        }
+        self.layout.split_focused(direction, self.view.split());
+        self.layout_windows();
+    }
+
+    ///关闭当前窗口，焦点移到占据其位置的窗口；buffer仍然保持打开
+    fn close_window(&mut self) {
+        match self.layout.close_focused() {
+            Some(view) => {
+                self.view = view;
+                self.view.snap_to_buffer();
+                self.layout_windows();
+                self.redraw_windows();
+                self.refresh_statusbar();
+                self.check_focused_disk_change();
+            },
+            None => self.message_bar.update_message("Cannot close the last window"),
+        }
+    }
+
+    ///调整当前窗口的大小
+    fn resize_window(&mut self, direction: Direction, delta: isize) {
+        if self.layout.resize_focused(direction, delta) {
+            self.layout_windows();
+        } else {
+            self.message_bar.update_message("No split to resize in that direction");
+        }
+    }
+
+    ///将焦点移到指定方向上相邻的窗口
+    fn move_focus(&mut self, move_command: command::Move) {
+        let (direction, forward) = match move_command {
+            command::Move::Up => (Direction::Horizontal, false),
+            command::Move::Down => (Direction::Horizontal, true),
+            command::Move::Left => (Direction::Vertical, false),
+            _ => (Direction::Vertical, true),
+        };
+        let Some(index) = self.layout.neighbour(self.text_area(), direction, forward) else {
+            return;
+        };
+        let focused = std::mem::take(&mut self.view);
+        self.view = self.layout.focus(index, focused);
+        self.view.snap_to_buffer();
+        self.refresh_statusbar();
+        self.check_focused_disk_change();
+    }
+
+    ///焦点移到其他窗口或切换buffer之后，检查新显示的文件是否在后台被其他程序修改
+    fn check_focused_disk_change(&mut self) {
+        if self.command_bar.is_none() {
+            self.check_disk_change();
+        }
+    }
+
     ///主要运行逻辑
     pub fn run(&mut self){ 
         loop {
@@ -123,14 +382,21 @@ impl Editor {
 
 
             if self.should_quit {
-                // let _ = Terminal::terminate();//离开副屏幕
+                let _ = Terminal::terminate();//关闭焦点报告和鼠标捕获、离开副屏幕
+                for buffer in &self.buffers {
+                    let _ = buffer.borrow_mut().remove_swap(); //正常退出，不再需要交换文件
+                }
                 break;
             }
 
-            match read() {
-                Ok(event) => {
+            //等待输入时设置超时，以便定时写入交换文件
+            let background = self.finder.as_ref().is_some_and(FileFinder::is_walking) || self.search.is_some();
+            let timeout = if background { BACKGROUND_TICK_INTERVAL } else { TICK_INTERVAL };
+            match poll(timeout).and_then(|ready| if ready { read().map(Some) } else { Ok(None) }) {
+                Ok(Some(event)) => {
                     self.evaluate_event(event);
                 },
+                Ok(None) => {},
                 Err(err) => {
                     #[cfg(debug_assertions)]
                     {
@@ -138,11 +404,24 @@ impl Editor {
                     }
                 }
             }
+            if let Some(finder) = &mut self.finder {
+                finder.receive_files();
+            }
+            self.receive_search_results();
+            self.update_swap_file();
+            if self.command_bar.is_none() && self.last_disk_check.elapsed() >= DISK_CHECK_INTERVAL {
+                self.check_disk_change();
+            }
+            if self.last_input.elapsed() >= self.settings.autosave.interval {
+                self.autosave();
+                self.last_input = Instant::now(); //保存失败时，等待下一个空闲周期再尝试
+            }
 
             //处理事件之后，可能文本内容发生改变，即statsbar的DocumentStatus内容改变，判断是否需要修改
             //处理事件之后，可能终端的size大小发生变化，进而导致状态栏的显示位置变化，已在size变更指令处处理了该逻辑
-            let status = self.view.get_status();
+            let status = self.active_view().get_status();
             self.status_bar.update_status(status);
+            self.tab_bar.update_tabs(self.tabs(), self.current_buffer_index());
         }
      
     }
@@ -150,6 +429,18 @@ impl Editor {
 
    
     fn evaluate_event(&mut self, event: Event) {
+        self.last_input = Instant::now();
+        if matches!(event, Event::FocusLost) {
+            self.autosave();
+            return;
+        }
+        if matches!(event, Event::FocusGained) {
+            if self.command_bar.is_none() {
+                self.check_disk_change();
+            }
+            return;
+        }
+
         let should_process = match event {
             Event::Key(KeyEvent{kind,..}) => {
                 kind == KeyEventKind::Press
@@ -157,6 +448,9 @@ impl Editor {
             Event::Resize(_,_ ) => {
                 true
             },
+            Event::Mouse(MouseEvent { kind, .. }) => {
+                kind == MouseEventKind::Down(MouseButton::Left)
+            },
             _ => false
         };
         
@@ -189,6 +483,12 @@ impl Editor {
             _ => self.reset_quit_times(),
         }
 
+        //buffer列表打开时，按键用于选择
+        if self.picker.is_some() && self.command_bar.is_none() {
+            self.handle_picker_command(&command);
+            return;
+        }
+
         match command {
            System(Quit | Resize(_)) => {}, //上面已经处理好了 
            System(Save) => {//当commandbar没有指令的时候，才进行正常的推出判断
@@ -196,40 +496,986 @@ impl Editor {
                     self.handle_save();
                 }
            },
-           System(Dismiss) => {
-                if self.command_bar.is_some() {
-                    self.dismiss_prompt();
-                    self.message_bar.update_message("save aborted");
+           System(Open) => {
+                if self.command_bar.is_none() && self.overlay.is_none() {
+                    self.show_open_prompt();
+                }
+           },
+           System(Dismiss) => self.handle_dismiss(),
+           System(Execute) => {
+                if self.command_bar.is_none() {
+                    self.show_command_prompt();
                 }
            },
-           Edit(edit_command) => {
-                if let Some(command_bar) = &mut self.command_bar {
-                    //command_bar存在，这里的操作是对command_bar的键入
-                    if matches!(edit_command,command::Edit::InsertNewline) {// 键入enter
-                        let file_name = command_bar.value();
-                        self.dismiss_prompt();
-                        self.save(Some(&file_name));
+           System(MoveFocus(move_command)) => {
+                if self.command_bar.is_none() && self.overlay.is_none() {
+                    self.move_focus(move_command);
+                }
+           },
+           System(SelectBuffer(index)) => {
+                if self.command_bar.is_none() && self.overlay.is_none() {
+                    if index < self.buffers.len() {
+                        self.switch_to_buffer(index);
                     } else {
-                        command_bar.handle_edit_command(edit_command);
+                        self.message_bar.update_message(&format!("No buffer {}", index.saturating_add(1)));
                     }
-                } else {
-                    self.view.handle_edit_command(edit_command); //command_bar不存在，正常键入view
                 }
            },
-           Move(move_command) => {
-                if self.command_bar.is_none() { //只处理view的光标移动
-                    self.view.handle_move_command(move_command);
+           System(Click(position)) => {
+                //点击标签栏切换buffer
+                if self.command_bar.is_none()
+                    && self.overlay.is_none()
+                    && position.row == 0
+                    && let Some(index) = self.tab_bar.tab_at(position.column)
+                {
+                    self.switch_to_buffer(index);
+                }
+           },
+           System(FindFile) => {
+                if self.command_bar.is_none() && self.overlay.is_none() {
+                    self.show_finder();
+                }
+           },
+           System(NextBuffer | PreviousBuffer | ListBuffers) => {
+                if self.command_bar.is_none() && self.overlay.is_none() {
+                    match command {
+                        System(NextBuffer) => self.cycle_buffer(true),
+                        System(PreviousBuffer) => self.cycle_buffer(false),
+                        _ => self.show_buffer_picker(),
+                    }
                 }
            },
+           Edit(edit_command) => self.handle_edit(edit_command),
+           Select(move_command) => self.handle_select(move_command),
+           Move(move_command) => self.handle_move(move_command),
+
+        }
+    }
+
+    ///Esc：关闭提示栏、临时内容或正在进行的搜索
+    fn handle_dismiss(&mut self) {
+        if self.command_bar.is_some() {
+            self.dismiss_prompt();
+            match self.prompt_type {
+                PromptType::Command | PromptType::CreateFile | PromptType::Rename(_) | PromptType::Open => {
+                    self.message_bar.update_message("");
+                },
+                PromptType::FindFile => self.close_finder(),
+                PromptType::Confirm(Confirmation::RecoverSwap(_)) => {
+                    //保留交换文件，本次编辑不再写入交换文件，避免覆盖
+                    self.view.suspend_swap();
+                    self.close_overlay();
+                    self.message_bar.update_message("Swap file kept");
+                },
+                PromptType::Confirm(Confirmation::ReloadChanged | Confirmation::ResolveConflict) => {
+                    //保留当前内容，不再提示磁盘上的这次修改
+                    self.view.acknowledge_disk_change();
+                    self.close_overlay();
+                    self.message_bar.update_message("Kept buffer contents");
+                },
+                _ => self.message_bar.update_message("save aborted"),
+            }
+        } else if self.overlay.is_some() {
+            self.close_overlay();
+        } else if self.search.is_some() {
+            self.cancel_search();
+        }
+    }
 
+    ///编辑类的按键：提示栏存在时键入提示栏，否则根据当前buffer的种类处理
+    fn handle_edit(&mut self, edit_command: command::Edit) {
+        if self.command_bar.is_some() {
+            //command_bar存在，这里的操作是对command_bar的键入
+            self.handle_prompt_edit(edit_command);
+        } else if self.overlay.is_some() {
+            self.message_bar.update_message("Read-only view, press Esc to close");
+        } else if self.view.is_directory() {
+            self.handle_directory_edit(edit_command);
+        } else if matches!(edit_command, command::Edit::InsertNewline) && self.view.is_search_results() {
+            self.open_search_result();
+        } else if let Err(message) = self.view.handle_edit_command(edit_command) { //command_bar不存在，正常键入view
+            self.message_bar.update_message(&message);
+        }
+    }
+
+    ///扩展选区，只在没有提示栏和临时内容时处理
+    fn handle_select(&mut self, move_command: command::Move) {
+        if self.command_bar.is_none() && self.overlay.is_none() {
+            self.view.handle_select_command(move_command);
+        }
+    }
+
+    ///光标移动：查找文件时移动列表中的选中项，否则移动view的光标
+    fn handle_move(&mut self, move_command: command::Move) {
+        if let Some(finder) = &mut self.finder {
+            finder.handle_move_command(move_command);
+        } else if self.command_bar.is_none() || self.overlay.is_some() { //只处理view的光标移动，提示栏存在时仍可以滚动临时内容
+            self.active_view().handle_move_command(move_command);
+        }
+    }
+
+    ///处理对 `command_bar` 的键入，根据 `prompt_type` 决定键入的含义
+    fn handle_prompt_edit(&mut self, edit_command: command::Edit) {
+        match &self.prompt_type {
+            PromptType::Save => {
+                if matches!(edit_command,command::Edit::InsertNewline) {// 键入enter
+                    let value = self.command_bar.as_ref().map(CommandBar::value).unwrap_or_default();
+                    let file_path = pathcompleter::resolve(&value, self.view.file_directory().as_deref());
+                    self.dismiss_prompt();
+                    self.save_as(file_path);
+                } else if let Some(command_bar) = &mut self.command_bar {
+                    command_bar.handle_edit_command(edit_command);
+                }
+            },
+            PromptType::Confirm(confirmation) => {
+                //只响应提示中的选项，其余按键忽略
+                let command::Edit::Insert(ch) = edit_command else {
+                    return;
+                };
+                let choice = ch.to_ascii_lowercase();
+                if !confirmation.choices().contains(&choice) {
+                    return;
+                }
+                let PromptType::Confirm(confirmation) = std::mem::take(&mut self.prompt_type) else {
+                    return;
+                };
+                self.dismiss_prompt();
+                self.confirm(confirmation, choice);
+            },
+            PromptType::Command => {
+                if matches!(edit_command,command::Edit::InsertNewline) {
+                    let value = self.command_bar.as_ref().map(CommandBar::value).unwrap_or_default();
+                    self.dismiss_prompt();
+                    match NamedCommand::try_from(value.as_str()) {
+                        Ok(named_command) => self.execute(&named_command),
+                        Err(err) => self.message_bar.update_message(&err),
+                    }
+                } else if let Some(command_bar) = &mut self.command_bar {
+                    command_bar.handle_edit_command(edit_command);
+                }
+            },
+            PromptType::FindFile => {
+                if matches!(edit_command,command::Edit::InsertNewline) {
+                    let selected = self.finder.as_ref().and_then(FileFinder::selected);
+                    self.dismiss_prompt();
+                    self.close_finder();
+                    if let Some(path) = selected {
+                        self.open_file(&path.to_string_lossy());
+                    }
+                } else if let Some(command_bar) = &mut self.command_bar {
+                    command_bar.handle_edit_command(edit_command);
+                    let query = command_bar.value();
+                    if let Some(finder) = &mut self.finder {
+                        finder.set_query(&query);
+                    }
+                }
+            },
+            PromptType::Open => {
+                if matches!(edit_command,command::Edit::InsertNewline) {
+                    let value = self.command_bar.as_ref().map(CommandBar::value).unwrap_or_default();
+                    self.dismiss_prompt();
+                    if value.is_empty() {
+                        self.message_bar.update_message("");
+                    } else {
+                        self.open_file(&value);
+                    }
+                } else if let Some(command_bar) = &mut self.command_bar {
+                    command_bar.handle_edit_command(edit_command);
+                }
+            },
+            PromptType::CreateFile | PromptType::Rename(_) => {
+                if matches!(edit_command,command::Edit::InsertNewline) {
+                    let value = self.command_bar.as_ref().map(CommandBar::value).unwrap_or_default();
+                    let path = pathcompleter::resolve(&value, self.view.file_directory().as_deref());
+                    let prompt_type = std::mem::take(&mut self.prompt_type);
+                    self.dismiss_prompt();
+                    if value.is_empty() {
+                        self.message_bar.update_message("");
+                    } else if let PromptType::Rename(from) = prompt_type {
+                        self.rename_entry(from, path);
+                    } else {
+                        self.create_entry(&path, value.ends_with('/'));
+                    }
+                } else if let Some(command_bar) = &mut self.command_bar {
+                    command_bar.handle_edit_command(edit_command);
+                }
+            },
+        }
+    }
+
+    ///目录列表中的按键：Enter 打开条目，Backspace 或 - 回到上一级目录，n 新建，r 重命名，d 删除
+    fn handle_directory_edit(&mut self, edit_command: command::Edit) {
+        match edit_command {
+            command::Edit::InsertNewline => self.open_directory_entry(),
+            command::Edit::DeleteBackward | command::Edit::Insert('-') => {
+                if let Some(parent) = self.view.file_path().as_deref().and_then(Path::parent) {
+                    self.open_directory(parent);
+                }
+            },
+            command::Edit::Insert('n') => {
+                self.show_directory_prompt("New file (end with / for a directory):", "", PromptType::CreateFile);
+            },
+            command::Edit::Insert('r') => {
+                if let Some(path) = self.selected_directory_entry() {
+                    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    self.show_directory_prompt(&format!("Rename {name} to:"), &name, PromptType::Rename(path));
+                }
+            },
+            command::Edit::Insert('d') | command::Edit::Delete => {
+                if let Some(path) = self.selected_directory_entry() {
+                    let question = format!("Delete {}?", path.display());
+                    self.show_confirmation(&question, Confirmation::DeleteEntry(path));
+                }
+            },
+            command::Edit::Insert(_) | command::Edit::Dedent | command::Edit::ToggleComment => {
+                self.message_bar.update_message("Enter: open | -: parent | n: new | r: rename | d: delete");
+            },
+        }
+    }
+
+    ///打开光标所在的条目：目录在当前列表中显示，文件作为新的buffer打开
+    fn open_directory_entry(&mut self) {
+        let Some(path) = self.view.selected_entry() else {
+            return;
+        };
+        if path.is_dir() {
+            self.open_directory(&path);
+        } else {
+            self.open_file(&path.to_string_lossy());
+        }
+    }
+
+    ///在当前的目录列表中显示另一个目录
+    fn open_directory(&mut self, path: &Path) {
+        match self.view.open_directory(path) {
+            Ok(()) => self.refresh_statusbar(),
+            Err(err) => self.message_bar.update_message(&format!("Could not open {}: {err}", path.display())),
+        }
+    }
+
+    ///打开查找文件的列表，从工作目录开始遍历
+    fn show_finder(&mut self) {
+        let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let mut finder = FileFinder::new(root);
+        finder.resize(self.text_area().size);
+        self.finder = Some(finder);
+        let mut command_bar = CommandBar::default();
+        command_bar.set_prompt("Find file:");
+        self.prompt_type = PromptType::FindFile;
+        self.open_command_bar(command_bar);
+    }
+
+    ///关闭查找文件的列表，后台的遍历随之停止
+    fn close_finder(&mut self) {
+        self.finder = None;
+        self.redraw_windows();
+        self.message_bar.update_message("");
+    }
+
+    ///在工作目录下的所有文件中搜索，结果显示在一个新的buffer中，搜索进行时按 Esc 取消
+    fn start_search(&mut self, pattern: &str, regex: bool) {
+        if let Some((search, _)) = &mut self.search {
+            search.cancel();
+        }
+        let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        match ProjectSearch::start(root, pattern, regex, self.settings.legacy_encoding) {
+            Ok(search) => {
+                let buffer = Rc::new(RefCell::new(Buffer::search_results(&format!("[grep {pattern}]"))));
+                self.buffers.push(Rc::clone(&buffer));
+                self.switch_to_buffer(self.buffers.len().saturating_sub(1));
+                self.search = Some((search, buffer));
+                self.message_bar.update_message(&format!("Searching for {pattern}, press Esc to cancel"));
+            },
+            Err(err) => self.message_bar.update_message(&err),
+        }
+    }
+
+    ///把后台搜索新找到的匹配加入结果buffer，搜索结束时给出匹配的数量
+    fn receive_search_results(&mut self) {
+        let Some((search, buffer)) = &mut self.search else {
+            return;
+        };
+        for found in search.receive() {
+            buffer.borrow_mut().append_result(&found.text, found.path, found.location);
+        }
+        if !search.is_running() {
+            let truncated = if search.is_truncated() { ", stopped early" } else { "" };
+            let message = format!("{} matches{truncated}", search.count());
+            self.search = None;
+            self.message_bar.update_message(&message);
+        }
+    }
+
+    ///取消正在进行的搜索，保留已经找到的结果
+    fn cancel_search(&mut self) {
+        if let Some((mut search, _)) = self.search.take() {
+            search.cancel();
+            self.message_bar.update_message(&format!("Search cancelled, {} matches", search.count()));
+        }
+    }
+
+    ///打开搜索结果中光标所在的匹配，光标移到匹配的位置
+    fn open_search_result(&mut self) {
+        let Some((path, location)) = self.view.selected_result() else {
+            return;
+        };
+        self.open_file(&path.to_string_lossy());
+        if self.view.is_current_file(&path) {
+            self.view.jump_to(location);
+        }
+    }
+
+    ///可以重命名或删除的条目，../ 除外
+    fn selected_directory_entry(&mut self) -> Option<PathBuf> {
+        let path = self.view.selected_entry()?;
+        if self.view.file_path().is_some_and(|directory| directory.starts_with(&path)) {
+            self.message_bar.update_message("Cannot modify the parent directory");
+            return None;
+        }
+        Some(path)
+    }
+
+    ///新建文件或目录，已经存在时不覆盖
+    fn create_entry(&mut self, path: &Path, directory: bool) {
+        let result = if directory {
+            fs::create_dir(path)
+        } else {
+            fs::File::create_new(path).map(|_| ())
+        };
+        match result {
+            Ok(()) => {
+                self.refresh_directory();
+                self.message_bar.update_message(&format!("Created {}", path.display()));
+            },
+            Err(err) => self.message_bar.update_message(&format!("Could not create {}: {err}", path.display())),
+        }
+    }
+
+    ///重命名条目，目标已经存在时需要先确认是否覆盖
+    fn rename_entry(&mut self, from: PathBuf, to: PathBuf) {
+        if to.exists() {
+            let question = format!("{} exists, overwrite?", to.display());
+            self.show_confirmation(&question, Confirmation::RenameEntry(from, to));
+        } else {
+            self.move_entry(&from, &to);
+        }
+    }
+
+    ///在磁盘上移动文件，已经打开该文件的buffer随之改用新的路径
+    fn move_entry(&mut self, from: &Path, to: &Path) {
+        //移动目录时，其中打开的文件也要更新路径
+        let source = fs::canonicalize(from).unwrap_or_else(|_| from.to_path_buf());
+        let opened: Vec<(Rc<RefCell<Buffer>>, PathBuf)> = self.buffers
+            .iter()
+            .filter_map(|buffer| {
+                let path = buffer.borrow().file_info.get_path().map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()))?;
+                let rest = path.strip_prefix(&source).ok()?;
+                let target = if rest.as_os_str().is_empty() { to.to_path_buf() } else { to.join(rest) };
+                Some((Rc::clone(buffer), target))
+            })
+            .collect();
+        match fs::rename(from, to) {
+            Ok(()) => {
+                for (buffer, target) in opened {
+                    buffer.borrow_mut().file_info.set_path(&target);
+                }
+                self.refresh_directory();
+                self.message_bar.update_message(&format!("Renamed to {}", to.display()));
+            },
+            Err(err) => self.message_bar.update_message(&format!("Could not rename {}: {err}", from.display())),
+        }
+    }
+
+    ///删除文件或空目录
+    fn delete_entry(&mut self, path: &Path) {
+        let result = if path.is_dir() { fs::remove_dir(path) } else { fs::remove_file(path) };
+        match result {
+            Ok(()) => {
+                self.refresh_directory();
+                self.message_bar.update_message(&format!("Deleted {}", path.display()));
+            },
+            Err(err) => self.message_bar.update_message(&format!("Could not delete {}: {err}", path.display())),
+        }
+    }
+
+    ///目录的内容变化之后重新读取列表
+    fn refresh_directory(&mut self) {
+        if let Err(err) = self.view.refresh_directory() {
+            self.message_bar.update_message(&format!("Could not read directory: {err}"));
+        }
+    }
+
+    ///执行命令提示栏中输入的指令
+    fn execute(&mut self, named_command: &NamedCommand) {
+        //会修改内容的指令在只读模式下拒绝执行
+        if matches!(
+            named_command,
+            NamedCommand::LineEnding(_) | NamedCommand::Encoding(_) | NamedCommand::RestoreBackup | NamedCommand::Retab(_) | NamedCommand::UndoSave
+        )
+            && let Err(message) = self.view.check_writable()
+        {
+            self.message_bar.update_message(&message);
+            return;
+        }
+
+        match named_command {
+            NamedCommand::LineEnding(ending) => {
+                self.view.convert_line_endings(*ending);
+                self.message_bar.update_message(&format!("Line endings converted to {}", self.view.get_status().line_ending.style));
+            },
+            NamedCommand::Reopen(encoding) => {
+                if self.view.get_status().is_modified {
+                    self.message_bar.update_message("Unsaved changes, save before reopening");
+                } else if let Err(err) = self.view.reload_with_encoding(*encoding) {
+                    self.message_bar.update_message(&format!("Could not reopen file: {err}"));
+                } else {
+                    let message = self.decode_warning().unwrap_or_else(|| format!("Reopened as {encoding}"));
+                    self.message_bar.update_message(&message);
+                }
+            },
+            NamedCommand::Encoding(encoding) => {
+                self.view.set_encoding(*encoding);
+                self.message_bar.update_message(&format!("File will be saved as {encoding}"));
+            },
+            NamedCommand::DiffBackup => {
+                let Some(backup) = self.view.newest_backup(&self.settings) else {
+                    self.message_bar.update_message("No backup found");
+                    return;
+                };
+                match self.view.diff_against(&backup) {
+                    Ok(lines) if lines.is_empty() => self.message_bar.update_message("No differences from backup"),
+                    Ok(lines) => self.show_overlay("[diff backup]", &lines),
+                    Err(err) => self.message_bar.update_message(&format!("Could not read backup: {err}")),
+                }
+            },
+            NamedCommand::RestoreBackup => {
+                let Some(backup) = self.view.newest_backup(&self.settings) else {
+                    self.message_bar.update_message("No backup found");
+                    return;
+                };
+                match self.view.restore_from(&backup) {
+                    Ok(()) => self.message_bar.update_message(&format!("Restored from {}, save to keep it", backup.display())),
+                    Err(err) => self.message_bar.update_message(&format!("Could not read backup: {err}")),
+                }
+            },
+            NamedCommand::Open(file_name) => self.open_file(file_name),
+            NamedCommand::NextBuffer => self.cycle_buffer(true),
+            NamedCommand::PreviousBuffer => self.cycle_buffer(false),
+            NamedCommand::ListBuffers => self.show_buffer_picker(),
+            NamedCommand::CloseBuffer(force) => self.close_buffer(self.current_buffer_index(), *force),
+            NamedCommand::Split(direction) => self.split_window(*direction),
+            NamedCommand::CloseWindow => self.close_window(),
+            NamedCommand::ResizeWindow(direction, delta) => self.resize_window(*direction, *delta),
+            NamedCommand::Focus(move_command) => self.move_focus(*move_command),
+            NamedCommand::Grep(pattern, regex) => self.start_search(pattern, *regex),
+            NamedCommand::Revert => self.handle_revert(),
+            NamedCommand::UndoSave => {
+                if self.view.undo_save_hooks() {
+                    self.message_bar.update_message("Restored contents from before the save cleanup");
+                } else {
+                    self.message_bar.update_message("Nothing to undo");
+                }
+            },
+            NamedCommand::Retab(expand) => {
+                let message = if self.view.retab(*expand, self.settings.indent_width) {
+                    format!("Converted indentation to {}", self.view.indentation())
+                } else {
+                    format!("Nothing to convert, indenting with {}", self.view.indentation())
+                };
+                self.message_bar.update_message(&message);
+            },
+            NamedCommand::Set(key, value) => {
+                //readonly 属于当前文件，其余为编辑器的设置
+                let result = if key == "readonly" {
+                    Settings::parse_bool(key, value).map(|read_only| self.view.set_read_only(read_only))
+                } else {
+                    self.settings.set(key, value)
+                };
+                //缩进的设置同时应用到当前buffer
+                if result.is_ok() && matches!(key.as_str(), "expandtab" | "indent_width") {
+                    self.view.set_indentation(Indentation::from_settings(self.settings.expandtab, self.settings.indent_width));
+                }
+                if result.is_ok() && key == "mouse" {
+                    let _ = Terminal::set_mouse_capture(self.settings.mouse);
+                }
+                //按文件类型的设置可能影响任何一个打开的buffer
+                if result.is_ok() && key.starts_with("autopairs") {
+                    for buffer in &self.buffers {
+                        let mut buffer = buffer.borrow_mut();
+                        buffer.options.auto_pairs = self.settings.autopairs.get(buffer.file_type());
+                    }
+                }
+                match result {
+                    Ok(()) => self.message_bar.update_message(&format!("{key} = {value}")),
+                    Err(err) => self.message_bar.update_message(&err),
+                }
+            },
+        }
+    }
+
+    ///当前接收光标移动的view：有临时内容时为临时内容
+    fn active_view(&mut self) -> &mut View {
+        self.overlay.as_mut().unwrap_or(&mut self.view)
+    }
+
+    ///在view的位置显示只读的临时内容
+    fn show_overlay(&mut self, title: &str, lines: &[String]) {
+        let mut overlay = View::scratch(title, lines);
+        let area = self.text_area();
+        overlay.set_area(area.origin, area.size);
+        self.overlay = Some(overlay);
+        self.message_bar.update_message("Press Esc to close");
+    }
+
+    ///关闭临时内容，重新显示view
+    fn close_overlay(&mut self) {
+        self.overlay = None;
+        self.redraw_windows();
+        self.message_bar.update_message("");
+    }
+
+    ///根据用户键入的选项，继续执行需要确认的操作
+    fn confirm(&mut self, confirmation: Confirmation, choice: char) {
+        match (confirmation, choice) {
+            (Confirmation::Overwrite(path), 'y') => self.save_with_directory_check(Some(path)),
+            (Confirmation::CreateDirectory(path), 'y') => {
+                if let Some(parent) = path.parent()
+                    && let Err(err) = fs::create_dir_all(parent)
+                {
+                    self.message_bar.update_message(&Self::describe_error(&err, parent));
+                    return;
+                }
+                self.save(Some(&path));
+            },
+            (Confirmation::RecoverSwap(swap), 'r') => {
+                self.close_overlay();
+                match self.view.recover_from_swap(&swap) {
+                    Ok(()) => self.message_bar.update_message("Recovered unsaved changes, save to keep them"),
+                    Err(err) => self.message_bar.update_message(&format!("Could not read swap file: {err}")),
+                }
+            },
+            (Confirmation::RecoverSwap(swap), 'd') => {
+                self.close_overlay();
+                match fs::remove_file(&swap) {
+                    Ok(()) => self.message_bar.update_message("Swap file discarded"),
+                    Err(err) => self.message_bar.update_message(&format!("Could not remove swap file: {err}")),
+                }
+            },
+            (Confirmation::RecoverSwap(swap), _) => {
+                match self.view.diff_swap(&swap) {
+                    Ok(lines) if lines.is_empty() => self.message_bar.update_message("Swap file has no unsaved changes"),
+                    Ok(lines) => self.show_overlay("[diff swap]", &lines),
+                    Err(err) => self.message_bar.update_message(&format!("Could not read swap file: {err}")),
+                }
+                //查看比较结果之后，仍需要选择恢复或者丢弃
+                self.ask_recover_swap(swap);
+            },
+            (Confirmation::ReloadChanged, 'y') | (Confirmation::ResolveConflict, 'r') => {
+                self.close_overlay();
+                match self.view.reload() {
+                    Ok(()) => {
+                        let message = self.decode_warning().unwrap_or_else(|| String::from("Reloaded from disk"));
+                        self.message_bar.update_message(&message);
+                    },
+                    Err(err) => self.message_bar.update_message(&format!("Could not reload file: {err}")),
+                }
+            },
+            (Confirmation::ReloadChanged, _) => {
+                self.view.acknowledge_disk_change();
+                self.message_bar.update_message("Kept buffer contents");
+            },
+            (Confirmation::ResolveConflict, 'o') => {
+                self.close_overlay();
+                self.save_with_directory_check(None);
+            },
+            (Confirmation::ResolveConflict, _) => {
+                let path = self.view.file_path().unwrap_or_default();
+                match self.view.diff_against(&path) {
+                    Ok(lines) if lines.is_empty() => self.message_bar.update_message("No differences from disk"),
+                    Ok(lines) => self.show_overlay("[diff disk]", &lines),
+                    Err(err) => self.message_bar.update_message(&format!("Could not read file: {err}")),
+                }
+                //查看比较结果之后，仍需要选择覆盖或者重新读取
+                self.ask_resolve_conflict();
+            },
+            (Confirmation::CloseBuffer(index), 'y') => self.close_buffer(index, true),
+            (Confirmation::Revert, 'y') => self.revert(),
+            (Confirmation::DeleteEntry(path), 'y') => self.delete_entry(&path),
+            (Confirmation::RenameEntry(from, to), 'y') => self.move_entry(&from, &to),
+            (Confirmation::CloseBuffer(_) | Confirmation::DeleteEntry(_) | Confirmation::RenameEntry(..) | Confirmation::Revert, _) => {
+                self.message_bar.update_message("");
+            },
+            _ => self.message_bar.update_message("save aborted"),
+        }
+    }
+
+    ///读取文件到新的buffer并显示，文件已经打开时切换到对应的buffer
+    fn open_file(&mut self, file_name: &str) {
+        let path = pathcompleter::resolve(file_name, self.view.file_directory().as_deref());
+        if let Some(index) = self.buffers.iter().position(|buffer| buffer.borrow().file_info.is_same_file(&path)) {
+            self.switch_to_buffer(index);
+            return;
+        }
+
+        //当前是一个空的未命名buffer时，用打开的文件代替它；有未保存修改的buffer不会被替换，仍留在列表中
+        let replace = {
+            let current = self.view.buffer().borrow();
+            !current.is_file_loaded() && !current.dirty && current.is_empty()
+        };
+        let current_index = self.current_buffer_index();
+        let path = path.to_string_lossy();
+        match self.load_buffer(&path, false) {
+            Ok(()) => {
+                if replace {
+                    self.buffers.remove(current_index);
+                }
+                self.switch_to_buffer(self.buffers.len().saturating_sub(1));
+            },
+            Err(err) => self.message_bar.update_message(&format!("Could not open {path}: {err}")),
+        }
+    }
+
+    ///放弃当前文件的修改，有未保存的修改时需要先确认
+    fn handle_revert(&mut self) {
+        if self.view.is_directory() {
+            self.refresh_directory();
+        } else if !self.view.is_file_loaded() || self.view.get_status().is_new_file {
+            self.message_bar.update_message("No file on disk to revert to");
+        } else if self.view.get_status().is_modified {
+            self.show_confirmation("Discard unsaved changes and reload from disk?", Confirmation::Revert);
+        } else {
+            self.revert();
+        }
+    }
+
+    ///重新读取磁盘上的文件，光标尽量留在原来的行
+    fn revert(&mut self) {
+        match self.view.reload() {
+            Ok(()) => {
+                let message = self.decode_warning().unwrap_or_else(|| String::from("Reverted to the file on disk"));
+                self.message_bar.update_message(&message);
+            },
+            Err(err) => self.message_bar.update_message(&format!("Could not reload file: {err}")),
+        }
+    }
+
+    ///文件中有无法按其编码解码的字节时的提示：这些字节已被替换，buffer 以只读模式打开
+    fn decode_warning(&self) -> Option<String> {
+        self.view.has_decode_errors().then(|| {
+            format!("Invalid {} bytes replaced, read-only; `set noreadonly` to save anyway", self.view.get_status().encoding)
+        })
+    }
+
+    ///没有打开任何文件时使用的空buffer，缩进方式来自设置
+    fn empty_buffer(&self) -> Rc<RefCell<Buffer>> {
+        let mut buffer = Buffer::default();
+        buffer.options = BufferOptions::from_settings(&self.settings, FileType::Text);
+        Rc::new(RefCell::new(buffer))
+    }
+
+    ///读取文件到view，并将其buffer加入列表的末尾
+    fn load_buffer(&mut self, file_name: &str, read_only: bool) -> Result<(),Error> {
+        self.view.load(file_name, &self.settings, read_only)?;
+        self.buffers.push(Rc::clone(self.view.buffer()));
+        Ok(())
+    }
+
+    ///view当前显示的buffer在列表中的位置
+    fn current_buffer_index(&self) -> usize {
+        self.buffers
+            .iter()
+            .position(|buffer| Rc::ptr_eq(buffer, self.view.buffer()))
+            .unwrap_or_default()
+    }
+
+    ///显示列表中的第index个buffer，第一次显示时检查交换文件
+    fn switch_to_buffer(&mut self, index: usize) {
+        let Some(buffer) = self.buffers.get(index).map(Rc::clone) else {
+            return;
+        };
+        self.view.set_buffer(Rc::clone(&buffer));
+        self.refresh_statusbar();
+        if self.buffers.len() > 1 {
+            self.message_bar.update_message(&format!("[{}/{}] {}", index.saturating_add(1), self.buffers.len(), self.view.get_status().file_name));
+        }
+
+        let first_time = !std::mem::replace(&mut buffer.borrow_mut().swap.checked, true);
+        if first_time
+            && self.command_bar.is_none()
+            && let Some(swap) = self.view.pending_swap()
+        {
+            //上一次编辑异常结束，询问是否恢复
+            self.ask_recover_swap(swap);
+        } else {
+            if first_time && let Some(warning) = self.decode_warning() {
+                self.message_bar.update_message(&warning);
+            }
+            self.check_focused_disk_change();
+        }
+    }
+
+    ///依次切换到下一个或上一个buffer
+    fn cycle_buffer(&mut self, forward: bool) {
+        let count = self.buffers.len();
+        if count < 2 {
+            self.message_bar.update_message("No other buffers");
+            return;
+        }
+        let current = self.current_buffer_index();
+        let next = if forward {
+            current.saturating_add(1) % count
+        } else {
+            current.checked_sub(1).unwrap_or(count - 1)
+        };
+        self.switch_to_buffer(next);
+    }
+
+    ///关闭第index个buffer，有未保存的修改且不是force时需要确认；关闭最后一个buffer时打开一个空buffer
+    fn close_buffer(&mut self, index: usize, force: bool) {
+        let Some(buffer) = self.buffers.get(index) else {
+            return;
+        };
+        let (name, dirty) = {
+            let buffer = buffer.borrow();
+            (buffer.file_info.to_string(), buffer.dirty)
+        };
+        if dirty && !force {
+            let question = format!("{name} has unsaved changes, close anyway?");
+            self.show_confirmation(&question, Confirmation::CloseBuffer(index));
+            return;
+        }
+
+        let is_current = index == self.current_buffer_index();
+        let buffer = self.buffers.remove(index);
+        let _ = buffer.borrow_mut().remove_swap();
+        if self.buffers.is_empty() {
+            self.buffers.push(self.empty_buffer());
+        }
+        let replacement = index.min(self.buffers.len().saturating_sub(1));
+        //其他窗口显示的是被关闭的buffer时，改为显示相邻的buffer
+        for view in self.layout.views_mut() {
+            if Rc::ptr_eq(view.buffer(), &buffer) {
+                view.set_buffer(Rc::clone(&self.buffers[replacement]));
+            }
+        }
+        if is_current {
+            self.switch_to_buffer(replacement);
+        }
+        self.refresh_buffer_picker();
+        self.message_bar.update_message(&format!("Closed {name}"));
+    }
+
+    ///标签栏中显示的每一个buffer
+    fn tabs(&self) -> Vec<Tab> {
+        self.buffers
+            .iter()
+            .map(|buffer| {
+                let buffer = buffer.borrow();
+                Tab {
+                    name: buffer.file_info.to_string(),
+                    is_modified: buffer.dirty,
+                }
+            })
+            .collect()
+    }
+
+    ///每一个buffer在列表中显示的内容：序号、名称以及是否修改
+    fn buffer_list(&self) -> Vec<String> {
+        self.buffers
+            .iter()
+            .enumerate()
+            .map(|(index, buffer)| {
+                let buffer = buffer.borrow();
+                let modified = if buffer.dirty { " (modified)" } else { "" };
+                format!("{:>3}: {}{modified}", index.saturating_add(1), buffer.file_info)
+            })
+            .collect()
+    }
+
+    ///在view的位置显示buffer列表
+    fn show_buffer_picker(&mut self) {
+        let mut picker = Picker::new(
+            "Buffers: Enter = open | Delete = close | Esc = cancel",
+            self.buffer_list(),
+            self.current_buffer_index(),
+        );
+        picker.resize(self.text_area().size);
+        self.picker = Some(picker);
+    }
+
+    ///buffer增减之后更新列表
+    fn refresh_buffer_picker(&mut self) {
+        let items = self.buffer_list();
+        if let Some(picker) = &mut self.picker {
+            picker.set_items(items);
+        }
+    }
+
+    ///关闭buffer列表，重新显示view
+    fn close_picker(&mut self) {
+        self.picker = None;
+        self.redraw_windows();
+    }
+
+    ///buffer列表打开时：上下移动选择，Enter 切换，Delete 关闭选中的buffer，Esc 取消
+    fn handle_picker_command(&mut self, command: &Command) {
+        let Some(picker) = &mut self.picker else {
+            return;
+        };
+        match command {
+            Move(move_command) => picker.handle_move_command(*move_command),
+            Edit(command::Edit::InsertNewline) => {
+                let index = picker.selected();
+                self.close_picker();
+                self.switch_to_buffer(index);
+            },
+            Edit(command::Edit::Delete) => {
+                let index = picker.selected();
+                self.close_buffer(index, false);
+            },
+            System(Dismiss) => self.close_picker(),
+            _ => {},
+        }
+    }
+
+    ///询问是否用交换文件恢复上一次未保存的修改
+    fn ask_recover_swap(&mut self, swap: PathBuf) {
+        let question = format!("Found swap file {}: [r]ecover, [d]iscard or [v]iew diff?", swap.display());
+        self.show_confirmation(&question, Confirmation::RecoverSwap(swap));
+    }
+
+    ///检查文件是否被其他程序修改，被修改时询问如何处理，返回是否显示了询问
+    /// 没有未保存的修改时询问是否重新读取；否则询问覆盖、重新读取或者查看差异
+    fn check_disk_change(&mut self) -> bool {
+        self.last_disk_check = Instant::now();
+        match self.view.check_disk() {
+            DiskChange::Unchanged => false,
+            DiskChange::Removed => {
+                self.message_bar.update_message("File was removed from disk, save to create it again");
+                false
+            },
+            DiskChange::Modified => {
+                let status = self.view.get_status();
+                if status.is_modified {
+                    self.ask_resolve_conflict();
+                } else {
+                    let question = format!("{} changed on disk, reload?", status.file_name);
+                    self.show_confirmation(&question, Confirmation::ReloadChanged);
+                }
+                true
+            },
+        }
+    }
+
+    ///文件被其他程序修改，且当前有未保存的修改时，询问如何处理
+    fn ask_resolve_conflict(&mut self) {
+        let question = format!(
+            "{} changed on disk and in the buffer: [o]verwrite, [r]eload or [d]iff?",
+            self.view.get_status().file_name
+        );
+        self.show_confirmation(&question, Confirmation::ResolveConflict);
+    }
+
+    ///开启自动保存时，保存所有已有文件名且有修改的buffer，包括其他标签页和分屏中的；正在输入提示栏时不保存
+    /// 文件被其他程序修改时不自动覆盖：当前buffer立即询问，其他buffer在切换到它时询问
+    fn autosave(&mut self) {
+        if !self.settings.autosave.enabled || self.command_bar.is_some() {
+            return;
+        }
+        if self.view.get_status().is_modified && self.check_disk_change() {
+            return;
+        }
+        let mut saved = 0_usize;
+        let mut error = None;
+        for buffer in &self.buffers {
+            let mut buffer = buffer.borrow_mut();
+            if !buffer.needs_autosave() || buffer.check_disk() == DiskChange::Modified {
+                continue;
+            }
+            match buffer.autosave() {
+                Ok(()) => {
+                    let _ = buffer.remove_swap();
+                    saved = saved.saturating_add(1);
+                },
+                Err(err) => {
+                    let target = buffer.file_info.get_path().map(Path::to_path_buf).unwrap_or_default();
+                    error = Some(Self::describe_error(&err, &target));
+                },
+            }
+        }
+        if let Some(error) = error {
+            self.message_bar.update_message(&error);
+        } else if saved == 1 {
+            self.message_bar.update_message("autosaved");
+        } else if saved > 1 {
+            self.message_bar.update_message(&format!("autosaved {saved} files"));
+        }
+    }
+
+    ///定时将所有buffer未保存的修改写入交换文件
+    fn update_swap_file(&mut self) {
+        if !self.settings.swap.enabled || self.last_swap_update.elapsed() < self.settings.swap.interval {
+            return;
+        }
+        self.last_swap_update = Instant::now();
+        for buffer in &self.buffers {
+            if let Err(err) = buffer.borrow_mut().update_swap() {
+                self.message_bar.update_message(&format!("Could not write swap file: {err}"));
+            }
         }
     }
 
     ///创建一个command_bar,并设置prompt,确定其显示位置，设置为需要渲染
-    fn show_prompt(&mut self) {
+    fn show_prompt(&mut self, prompt: &str) {
+        let mut command_bar = CommandBar::default();
+        command_bar.set_prompt(prompt);
+        command_bar.enable_path_completion(self.view.file_directory());
+        self.prompt_type = PromptType::Save;
+        self.open_command_bar(command_bar);
+    }
+
+    ///目录列表中新建或重命名时的输入提示，相对路径以列表的目录为基准
+    fn show_directory_prompt(&mut self, prompt: &str, value: &str, prompt_type: PromptType) {
+        let mut command_bar = CommandBar::default();
+        command_bar.set_prompt(prompt);
+        command_bar.set_value(value);
+        command_bar.enable_path_completion(self.view.file_directory());
+        self.prompt_type = prompt_type;
+        self.open_command_bar(command_bar);
+    }
+
+    ///输入要打开的文件，相对路径以当前文件所在的目录为基准，Tab键补全
+    fn show_open_prompt(&mut self) {
+        let mut command_bar = CommandBar::default();
+        command_bar.set_prompt("Open:");
+        command_bar.enable_path_completion(self.view.file_directory());
+        self.prompt_type = PromptType::Open;
+        self.open_command_bar(command_bar);
+    }
+
+    ///显示命令提示栏
+    fn show_command_prompt(&mut self) {
+        let mut command_bar = CommandBar::default();
+        command_bar.set_prompt("Command:");
+        self.prompt_type = PromptType::Command;
+        self.open_command_bar(command_bar);
+    }
+
+    ///显示一个 y/n 的确认提示
+    fn show_confirmation(&mut self, question: &str, confirmation: Confirmation) {
         let mut command_bar = CommandBar::default();
-        command_bar.set_prompt("Save as:");
+        //y/n 以外的选项已经写在问题中
+        if confirmation.choices() == ['y', 'n'] {
+            command_bar.set_prompt(&format!("{question} (y/n)"));
+        } else {
+            command_bar.set_prompt(question);
+        }
+        self.prompt_type = PromptType::Confirm(confirmation);
+        self.open_command_bar(command_bar);
+    }
 
+    ///确定 `command_bar` 的显示位置，设置为需要渲染
+    fn open_command_bar(&mut self, mut command_bar: CommandBar) {
         //确定显示位置
         command_bar.resize( Size {
             columns: self.terminal_size.columns,
@@ -248,36 +1494,100 @@ impl Editor {
         self.message_bar.set_needs_redraw(true);
     }
 
-    ///当文件没有被修改的时候，可以直接退出;当quit_times == 3 的时候可以直接退出;其余，增加quit_times 的次数
+    ///当所有buffer都没有被修改的时候，可以直接退出;当 `quit_times` == 3 的时候可以直接退出;其余，增加 `quit_times` 的次数
     fn handle_quit(&mut self) {
-       if !self.view.get_status().is_modified || self.quit_times + 1 == QUIT_TIMES {
+       let dirty: Vec<String> = self.buffers
+            .iter()
+            .filter(|buffer| buffer.borrow().dirty)
+            .map(|buffer| buffer.borrow().file_info.to_string())
+            .collect();
+       if dirty.is_empty() || self.quit_times + 1 == QUIT_TIMES {
             self.should_quit = true;
-       } else if self.view.get_status().is_modified {
+       } else {
             self.quit_times += 1;  
+            self.message_bar.update_message(&format!(
+                "WARNING! Unsaved changes in {}. Press Ctrl-Q {} more times to quit.",
+                dirty.join(", "),
+                QUIT_TIMES - self.quit_times
+            ));
        } 
     }
 
     ///处理保存指令
     fn handle_save(&mut self) {
-        if self.view.is_file_loaded() { 
-            self.save(None);
+        if self.view.is_directory() {
+            self.message_bar.update_message("Directory listing cannot be saved");
+        } else if self.view.get_status().is_read_only {
+            //只读的文件不能覆盖，建议另存为
+            self.show_prompt("File is read-only, save as:");
+        } else if self.view.is_file_loaded() { 
+            //文件被其他程序修改时，先询问是否覆盖
+            if !self.check_disk_change() {
+                self.save_with_directory_check(None);
+            }
+        } else {
+            self.show_prompt("Save as:");
+        }
+    }
+
+    ///另存为：目标是一个已经存在的其他文件时，需要先确认是否覆盖
+    fn save_as(&mut self, file_path: PathBuf) {
+        if file_path.exists() && !self.view.is_current_file(&file_path) {
+            let question = format!("{} exists, overwrite?", file_path.display());
+            self.show_confirmation(&question, Confirmation::Overwrite(file_path));
+        } else {
+            self.save_with_directory_check(Some(file_path));
+        }
+    }
+
+    ///父目录不存在时，需要先确认是否创建
+    fn save_with_directory_check(&mut self, file_path: Option<PathBuf>) {
+        let missing_parent = file_path
+            .as_deref()
+            .or(self.view.file_path().as_deref())
+            .and_then(Path::parent)
+            .filter(|parent| !parent.as_os_str().is_empty() && !parent.exists())
+            .map(Path::to_path_buf);
+
+        if let Some(parent) = missing_parent {
+            let target = file_path.or_else(|| self.view.file_path()).unwrap_or_default();
+            let question = format!("Directory {} does not exist, create it?", parent.display());
+            self.show_confirmation(&question, Confirmation::CreateDirectory(target));
         } else {
-            self.show_prompt();
+            self.save(file_path.as_deref());
         }
     }
 
     ///保存文件到指定路径,如果当前接受的路径为空，则保存到当前文件
-    fn save(&mut self,file_name: Option<&str>) {
-        let result = if let Some(name) = file_name {
-            self.view.save_as(name)
+    fn save(&mut self,file_path: Option<&Path>) {
+        let result = if let Some(path) = file_path {
+            self.view.save_as(path, &self.settings)
         } else {
-            self.view.save() 
+            self.view.save(&self.settings) 
         }; 
 
-        if result.is_ok() {
-            self.message_bar.update_message("File saved successfully.");
-        } else {
-            self.message_bar.update_message("Error writing file");
+        match result {
+            Ok(()) => {
+                let _ = self.view.remove_swap(); //保存之后交换文件不再需要
+                self.message_bar.update_message("File saved successfully.");
+            },
+            Err(err) if err.kind() == ErrorKind::PermissionDenied && file_path.is_none() => {
+                //没有权限写入当前文件，建议另存为
+                self.show_prompt("Permission denied, save as:");
+            },
+            Err(err) => {
+                let target = file_path.map(Path::to_path_buf).or_else(|| self.view.file_path()).unwrap_or_default();
+                self.message_bar.update_message(&Self::describe_error(&err, &target));
+            },
+        }
+    }
+
+    ///将写文件时的错误转换为用户可读的信息
+    fn describe_error(err: &Error, path: &Path) -> String {
+        match err.kind() {
+            ErrorKind::PermissionDenied => format!("Permission denied: {}", path.display()),
+            ErrorKind::NotFound => format!("No such file or directory: {}", path.display()),
+            _ => format!("Error writing file {}: {err}", path.display()),
         }
     }
 
@@ -310,9 +1620,19 @@ impl Editor {
             self.status_bar.render(self.terminal_size.rows.saturating_sub(2));
        } 
 
-       //渲染View
+       //渲染标签栏和View，buffer列表打开时显示列表
        if self.terminal_size.rows > 2 {
-            self.view.render(0);
+            self.tab_bar.render(0);
+            let text_row = self.text_area().origin.row;
+            if let Some(finder) = &mut self.finder {
+                finder.render(text_row);
+            } else if let Some(picker) = &mut self.picker {
+                picker.render(text_row);
+            } else if let Some(overlay) = &mut self.overlay {
+                overlay.render(text_row);
+            } else {
+                self.render_windows();
+            }
        } 
 
       //渲染之后的光标位置 
@@ -321,8 +1641,13 @@ impl Editor {
                 column: command_bar.caret_position_end(),
                 row: bottom_bar_row,
             }
+       } else if let Some(picker) = &self.picker {
+            Position {
+                column: 0,
+                row: picker.caret_row().saturating_add(self.text_area().origin.row),
+            }
        } else {
-            self.view.caret_position()
+            self.active_view().caret_position()
        };
 
         //移动光标
@@ -363,6 +1688,18 @@ impl Default for Editor {
             title: String::default(), 
             quit_times: 0,
             command_bar: None,
+            prompt_type: PromptType::default(),
+            settings: Settings::default(),
+            overlay: None,
+            last_swap_update: Instant::now(),
+            last_input: Instant::now(),
+            last_disk_check: Instant::now(),
+            buffers: Vec::new(),
+            picker: None,
+            layout: Layout::default(),
+            tab_bar: TabBar::default(),
+            finder: None,
+            search: None,
         }
     }
-}
\ No newline at end of file
+}
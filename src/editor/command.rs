@@ -3,9 +3,12 @@ use crossterm::event::{
 };
 
 
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use std::{any::Any, convert::TryFrom};
 use crate::editor::size::Size;
+use crate::editor::position::Position;
 ///移动指令枚举
+#[derive(Clone, Copy)]
 pub enum Move {
     Pageup,
     PageDown,
@@ -15,6 +18,7 @@ pub enum Move {
     Left,
     Right,
     Down,
+    MatchingBracket, //Ctrl-]，跳到与光标处的括号配对的括号
 }
 
 
@@ -44,6 +48,9 @@ impl TryFrom<KeyEvent> for Move {
 
                 _ => { Err(format!("unsupported key code {code:?} or modifier {modifiers:?}" ))},
             }
+        } else if modifiers == KeyModifiers::CONTROL && matches!(code, KeyCode::Char(']' | '5')) {
+            //多数终端把 Ctrl-] 报告为 Ctrl-5
+            Ok(Self::MatchingBracket)
         } else {
             Err(format!("unsupported key code {code:?} or modifier {modifiers:?}" ))
         }
@@ -51,11 +58,14 @@ impl TryFrom<KeyEvent> for Move {
 }
 
 ///编辑指令类型
+#[derive(Clone, Copy)]
 pub enum Edit {
    Insert(char),
    InsertNewline, //enter
    Delete,
    DeleteBackward, //Backsapce
+   Dedent, //Shift-Tab，减少一级缩进
+   ToggleComment, //Ctrl-/，注释或取消注释
 }
 
 impl TryFrom<KeyEvent> for Edit  {
@@ -70,6 +80,9 @@ impl TryFrom<KeyEvent> for Edit  {
         match (code,modifiers) {
             (KeyCode::Char(ch),KeyModifiers::NONE | KeyModifiers::SHIFT) => { Ok(Edit::Insert(ch))},
             (KeyCode::Tab,KeyModifiers::NONE) => {Ok(Edit::Insert('\t'))},
+            (KeyCode::BackTab,KeyModifiers::NONE | KeyModifiers::SHIFT) => {Ok(Edit::Dedent)},
+            //多数终端把 Ctrl-/ 报告为 Ctrl-7
+            (KeyCode::Char('/' | '7'),KeyModifiers::CONTROL) => {Ok(Edit::ToggleComment)},
             (KeyCode::Enter,KeyModifiers::NONE) => { Ok(Edit::InsertNewline)},
 
             (KeyCode::Delete,KeyModifiers::NONE) => {Ok(Edit::Delete)},
@@ -88,6 +101,15 @@ pub enum System {
     Save,
     Quit,
     Dismiss,
+    Execute, //打开命令提示栏
+    Open, //输入要打开的文件，Ctrl-O
+    NextBuffer, //切换到下一个buffer
+    PreviousBuffer, //切换到上一个buffer
+    FindFile, //模糊查找工作目录下的文件，Ctrl-P
+    ListBuffers, //列出所有打开的buffer
+    MoveFocus(Move), //分屏时将焦点移到相邻的窗口，Alt+方向键
+    SelectBuffer(usize), //切换到第几个buffer，Alt+1..9
+    Click(Position), //鼠标左键点击的位置
 }
 
 impl TryFrom<KeyEvent> for System  {
@@ -100,6 +122,20 @@ impl TryFrom<KeyEvent> for System  {
            (KeyCode::Char('q'),KeyModifiers::CONTROL) => {Ok(System::Quit)},
            (KeyCode::Char('s'),KeyModifiers::CONTROL) => { Ok(System::Save)},
            (KeyCode::Esc,KeyModifiers::NONE) => { Ok(System::Dismiss)}
+           (KeyCode::Char('e'),KeyModifiers::CONTROL) => { Ok(System::Execute)},
+           (KeyCode::Char('o'),KeyModifiers::CONTROL) => { Ok(System::Open)},
+           (KeyCode::PageDown,KeyModifiers::CONTROL) => { Ok(System::NextBuffer)},
+           (KeyCode::PageUp,KeyModifiers::CONTROL) => { Ok(System::PreviousBuffer)},
+           (KeyCode::Char('b'),KeyModifiers::CONTROL) => { Ok(System::ListBuffers)},
+           (KeyCode::Char('p'),KeyModifiers::CONTROL) => { Ok(System::FindFile)},
+           (KeyCode::Up,KeyModifiers::ALT) => { Ok(System::MoveFocus(Move::Up))},
+           (KeyCode::Down,KeyModifiers::ALT) => { Ok(System::MoveFocus(Move::Down))},
+           (KeyCode::Left,KeyModifiers::ALT) => { Ok(System::MoveFocus(Move::Left))},
+           (KeyCode::Right,KeyModifiers::ALT) => { Ok(System::MoveFocus(Move::Right))},
+           (KeyCode::Char(ch @ '1'..='9'),KeyModifiers::ALT) => {
+                let number = ch.to_digit(10).and_then(|digit| usize::try_from(digit).ok()).unwrap_or(1);
+                Ok(System::SelectBuffer(number.saturating_sub(1)))
+           },
             _ => Err(format!("unsupported key code {code:?} or modifier {modifiers:?}")),
        } 
 
@@ -109,10 +145,21 @@ impl TryFrom<KeyEvent> for System  {
 ///将设计的几种指令整合在一起，抽象
 pub enum Command {
    Move(Move),
+   Select(Move), //按住Shift移动光标，扩展选择的范围
    Edit(Edit),
    System(System), 
 }
 
+impl Command {
+    ///Shift 加上移动按键，表示选择
+    fn select_from(key_event: KeyEvent) -> Result<Self, String> {
+        if key_event.modifiers != KeyModifiers::SHIFT {
+            return Err(format!("not a selection: {key_event:?}"));
+        }
+        Move::try_from(KeyEvent { modifiers: KeyModifiers::NONE, ..key_event }).map(Self::Select)
+    }
+}
+
 ///事件不仅涉及按键，还有屏幕尺寸的更改，所以这里的dy是Event
 impl TryFrom<Event> for Command {
    type Error = String; 
@@ -125,9 +172,13 @@ impl TryFrom<Event> for Command {
                 Edit::try_from(key_event)
                     .map(Command::Edit)
                     .or_else(|_| Move::try_from(key_event).map(Command::Move))
+                    .or_else(|_| Self::select_from(key_event))
                     .or_else(|_| System::try_from(key_event).map(Command::System))
                     .map_err(|_errr| format!("Event not surpported: {key_event:?}"))
             },
+            Event::Mouse(MouseEvent { kind: MouseEventKind::Down(MouseButton::Left), column, row, .. }) => {
+                Ok(Self::System(System::Click(Position { column: usize::from(column), row: usize::from(row) })))
+            },
             Event::Resize(columns,rows ) => {
                 Ok( 
                     Self::System(
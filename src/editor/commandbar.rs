@@ -1,7 +1,9 @@
 use std::{cmp::min,io::Error};
+use std::path::PathBuf;
 use crate::editor::terminal::{ Terminal};
 use super::{UIComponent};
 use super::line::Line;
+use super::pathcompleter::PathCompleter;
 use crate::editor::command::{Edit};
 use crate::editor::size::Size;
 
@@ -12,22 +14,47 @@ pub struct CommandBar {
     value: Line,
     needs_redraw: bool,
     size: Size,
+    completer: Option<PathCompleter>, //开启路径补全后，Tab键用于补全路径
+    candidates: Vec<String>, //第二次Tab时列出的候选项，显示在输入之后
 }
 
 
 impl CommandBar {
    ///处理对commandbar的编辑指令
    pub fn handle_edit_command(&mut self, command: Edit ) {
+        self.candidates.clear();
+        if matches!(command, Edit::Insert('\t')) && self.completer.is_some() {
+            self.complete_path();
+            self.set_needs_redraw(true);
+            return;
+        }
+        if let Some(completer) = &mut self.completer {
+            completer.reset();
+        }
+
         match command {
            Edit::Insert(ch) => {self.value.append_char(ch);},
-           Edit::InsertNewline => {},
-           Edit::Delete => {},
+           Edit::InsertNewline | Edit::Delete | Edit::Dedent | Edit::ToggleComment => {},
            Edit::DeleteBackward => { self.value.delete_last();},
         }
 
         self.set_needs_redraw(true);
    } 
 
+   ///开启路径补全，相对路径以 `base_dir` 为基准
+   pub fn enable_path_completion(&mut self, base_dir: Option<PathBuf>) {
+        self.completer = Some(PathCompleter::new(base_dir));
+   }
+
+   ///Tab键补全路径，无法继续补全时再次按Tab列出候选项
+   fn complete_path(&mut self) {
+        if let Some(completer) = &mut self.completer {
+            let completion = completer.complete(&self.value.to_string());
+            self.value = Line::from(&completion.value);
+            self.candidates = completion.candidates;
+        }
+   }
+
    pub fn caret_position_end(&self) -> usize {
         let max_width = self.prompt.len().saturating_add(self.value.grapheme_count());
         min(max_width, self.size.columns)
@@ -40,6 +67,11 @@ impl CommandBar {
    pub fn set_prompt(&mut self, prompt: &str) {
         self.prompt = prompt.to_string()
    }
+
+   ///预先填入的输入内容，如重命名时原来的名称
+   pub fn set_value(&mut self, value: &str) {
+        self.value = Line::from(value);
+   }
 }
 
 impl UIComponent for CommandBar {
@@ -66,7 +98,18 @@ impl UIComponent for CommandBar {
        let value_start =  value_end.saturating_sub(area_for_value);
 
        //用于打印的信息
-        let message = format!("{}{}",self.prompt,self.value.get_visible_graheme(value_start..value_end));
+        let mut message = format!("{}{}",self.prompt,self.value.get_visible_graheme(value_start..value_end));
+
+        //候选项显示在输入之后，超出宽度的部分不显示
+        if !self.candidates.is_empty() {
+            let hint = format!("  [{}]", self.candidates.join(" "));
+            for ch in hint.chars() {
+                if message.len().saturating_add(ch.len_utf8()) > self.size.columns {
+                    break;
+                }
+                message.push(ch);
+            }
+        }
 
         let to_print = if message.len() <= self.size.columns {
             message
@@ -0,0 +1,177 @@
+const CONTEXT_LINES: usize = 3; //每个改动前后显示的上下文行数
+const MAX_TABLE_CELLS: usize = 4_000_000; //LCS 表格的上限，超过时视为整体替换
+
+///逐行比较的一步操作
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+///比较两段文本，生成统一格式(unified)的差异，两者相同时返回空
+pub fn unified_diff(old_name: &str, new_name: &str, old: &[String], new: &[String]) -> Vec<String> {
+    let ops = diff_lines(old, new);
+    if ops.iter().all(|op| *op == DiffOp::Equal) {
+        return Vec::new();
+    }
+
+    //每一步操作之前，旧文本和新文本已经走过的行数
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut old_index, mut new_index) = (0_usize, 0_usize);
+    for op in &ops {
+        positions.push((old_index, new_index));
+        match op {
+            DiffOp::Equal => {
+                old_index = old_index.saturating_add(1);
+                new_index = new_index.saturating_add(1);
+            }
+            DiffOp::Delete => old_index = old_index.saturating_add(1),
+            DiffOp::Insert => new_index = new_index.saturating_add(1),
+        }
+    }
+
+    let mut output = vec![format!("--- {old_name}"), format!("+++ {new_name}")];
+    for (start, end) in hunks(&ops) {
+        let (old_start, new_start) = positions[start];
+        let old_count = ops[start..end].iter().filter(|op| **op != DiffOp::Insert).count();
+        let new_count = ops[start..end].iter().filter(|op| **op != DiffOp::Delete).count();
+        output.push(format!(
+            "@@ -{},{old_count} +{},{new_count} @@",
+            old_start.saturating_add(1),
+            new_start.saturating_add(1)
+        ));
+
+        for (op, (old_index, new_index)) in ops[start..end].iter().zip(&positions[start..end]) {
+            match op {
+                DiffOp::Equal => output.push(format!(" {}", old[*old_index])),
+                DiffOp::Delete => output.push(format!("-{}", old[*old_index])),
+                DiffOp::Insert => output.push(format!("+{}", new[*new_index])),
+            }
+        }
+    }
+    output
+}
+
+///将改动连同上下文划分为若干段，相邻的段会合并
+fn hunks(ops: &[DiffOp]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, op) in ops.iter().enumerate() {
+        if *op == DiffOp::Equal {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = index.saturating_add(CONTEXT_LINES).saturating_add(1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+///基于最长公共子序列的逐行比较，相同的开头和结尾不参与计算
+fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffOp> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len().saturating_sub(suffix)];
+    let new_middle = &new[prefix..new.len().saturating_sub(suffix)];
+
+    let mut ops = vec![DiffOp::Equal; prefix];
+    ops.extend(diff_middle(old_middle, new_middle));
+    ops.extend(vec![DiffOp::Equal; suffix]);
+    ops
+}
+
+fn diff_middle(old: &[String], new: &[String]) -> Vec<DiffOp> {
+    let columns = new.len().saturating_add(1);
+    if old.len().saturating_add(1).saturating_mul(columns) > MAX_TABLE_CELLS {
+        let mut ops = vec![DiffOp::Delete; old.len()];
+        ops.extend(vec![DiffOp::Insert; new.len()]);
+        return ops;
+    }
+
+    //lengths[i * columns + j] 表示 old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut lengths = vec![0_usize; old.len().saturating_add(1).saturating_mul(columns)];
+    let cell = |i: usize, j: usize| i.saturating_mul(columns).saturating_add(j);
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[cell(i, j)] = if old[i] == new[j] {
+                lengths[cell(i.saturating_add(1), j.saturating_add(1))].saturating_add(1)
+            } else {
+                lengths[cell(i.saturating_add(1), j)].max(lengths[cell(i, j.saturating_add(1))])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Equal);
+            i = i.saturating_add(1);
+            j = j.saturating_add(1);
+        } else if lengths[cell(i.saturating_add(1), j)] >= lengths[cell(i, j.saturating_add(1))] {
+            ops.push(DiffOp::Delete);
+            i = i.saturating_add(1);
+        } else {
+            ops.push(DiffOp::Insert);
+            j = j.saturating_add(1);
+        }
+    }
+    ops.extend(vec![DiffOp::Delete; old.len().saturating_sub(i)]);
+    ops.extend(vec![DiffOp::Insert; new.len().saturating_sub(j)]);
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn identical_texts_have_no_diff() {
+        assert!(unified_diff("a", "b", &lines("x\ny"), &lines("x\ny")).is_empty());
+    }
+
+    #[test]
+    fn single_change_with_context() {
+        let old = lines("1\n2\n3\n4\n5\n6\n7\n8\n9");
+        let new = lines("1\n2\n3\n4\nfive\n6\n7\n8\n9");
+        assert_eq!(
+            unified_diff("old", "new", &old, &new),
+            vec!["--- old", "+++ new", "@@ -2,7 +2,7 @@", " 2", " 3", " 4", "-5", "+five", " 6", " 7", " 8"],
+        );
+    }
+
+    #[test]
+    fn insertions_and_deletions_at_the_ends() {
+        assert_eq!(
+            unified_diff("old", "new", &lines("a\nb"), &lines("b\nc")),
+            vec!["--- old", "+++ new", "@@ -1,2 +1,2 @@", "-a", " b", "+c"],
+        );
+        assert_eq!(
+            unified_diff("old", "new", &[], &lines("a")),
+            vec!["--- old", "+++ new", "@@ -1,0 +1,1 @@", "+a"],
+        );
+    }
+
+    #[test]
+    fn distant_changes_form_separate_hunks() {
+        let old: Vec<String> = (0..20).map(|index| index.to_string()).collect();
+        let mut new = old.clone();
+        new[1] = String::from("one");
+        new[18] = String::from("eighteen");
+        let diff = unified_diff("old", "new", &old, &new);
+        let headers: Vec<&String> = diff.iter().filter(|line| line.starts_with("@@")).collect();
+        assert_eq!(headers, vec!["@@ -1,5 +1,5 @@", "@@ -16,5 +16,5 @@"]);
+    }
+}
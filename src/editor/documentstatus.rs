@@ -1,10 +1,24 @@
 
+use crate::editor::lineending::LineEndingStyle;
+use crate::editor::encoding::FileEncoding;
+
+///文件的换行风格，以及最后一行是否没有换行符
+#[derive(Default,Clone,Copy,Eq,PartialEq,Debug)]
+pub struct LineEndingStatus {
+    pub style: LineEndingStyle,
+    pub no_final_newline: bool,
+}
+
 ///记录文件状态的结构体
 #[derive(Default,Eq,PartialEq,Debug)]
 pub struct DocumentStatus {
     pub total_lines:usize,
     pub current_line_index: usize,
     pub is_modified: bool,
+    pub is_new_file: bool,
+    pub is_read_only: bool,
+    pub line_ending: LineEndingStatus,
+    pub encoding: FileEncoding,
     pub file_name: String,
 }
 
@@ -19,11 +33,38 @@ impl DocumentStatus {
         }
     }
 
+    ///文件尚未保存到磁盘时显示 [New File]
+    pub fn new_file_indicator_to_string(&self) -> String {
+        if self.is_new_file {
+            String::from("[New File]")
+        } else {
+            String::new()
+        }
+    }
+
+    ///只读模式下显示 [RO]
+    pub fn read_only_indicator_to_string(&self) -> String {
+        if self.is_read_only {
+            String::from("[RO]")
+        } else {
+            String::new()
+        }
+    }
+
     ///展示当前文件的全部行号 xx lines
     pub fn line_count_to_string(&self) -> String {
         format!("{} lines",self.total_lines)
     }
 
+    ///文件的换行风格，最后一行没有换行符时追加 noeol，如 CRLF noeol
+    pub fn line_ending_indicator_to_string(&self) -> String {
+        if self.line_ending.no_final_newline {
+            format!("{} noeol", self.line_ending.style)
+        } else {
+            self.line_ending.style.to_string()
+        }
+    }
+
     ///当前所在行号 2/23
     pub fn position_indicator_to_string(&self) -> String {
         format!("{}/{}",self.current_line_index.saturating_add(1),self.total_lines)
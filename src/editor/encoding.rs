@@ -0,0 +1,185 @@
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use std::{
+    fmt::{self, Display},
+    io::{Error, ErrorKind},
+};
+
+const UTF_8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF_16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF_16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+///文件的字符编码，以及文件开头是否带有 BOM
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct FileEncoding {
+    encoding: &'static Encoding,
+    bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl FileEncoding {
+    ///根据文件内容判断编码：优先识别 BOM，其次判断是否为合法的 UTF-8，都不是时使用配置的旧式编码
+    pub fn detect(bytes: &[u8], fallback: &'static Encoding) -> Self {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return Self {
+                encoding,
+                bom: true,
+            };
+        }
+        if std::str::from_utf8(bytes).is_ok() {
+            Self::default()
+        } else {
+            Self {
+                encoding: fallback,
+                bom: false,
+            }
+        }
+    }
+
+    ///由名称解析编码，如 utf-8、utf-8-bom、utf-16le、gbk、latin1
+    pub fn from_label(label: &str) -> Option<Self> {
+        let lowercase = label.to_ascii_lowercase();
+        let (name, bom) = match lowercase.strip_suffix("-bom") {
+            Some(name) => (name, true),
+            None => (lowercase.as_str(), false),
+        };
+        let encoding = Encoding::for_label(name.as_bytes())?;
+        let is_unicode = encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE;
+        if bom && !is_unicode {
+            return None;
+        }
+        //UTF-16 文件总是带有 BOM，否则无法识别
+        let bom = bom || encoding == UTF_16LE || encoding == UTF_16BE;
+        Some(Self { encoding, bom })
+    }
+
+    ///将文件内容解码为字符串，去掉开头的 BOM；无法解码的字节替换为 U+FFFD
+    /// 同时返回是否有这样的字节，此时原来的字节已经丢失，保存会改变文件
+    pub fn decode(self, bytes: &[u8]) -> (String, bool) {
+        let bytes = if self.bom {
+            bytes
+                .strip_prefix(self.bom_bytes())
+                .unwrap_or(bytes)
+        } else {
+            bytes
+        };
+        let (contents, had_errors) = self.encoding.decode_without_bom_handling(bytes);
+        (contents.into_owned(), had_errors)
+    }
+
+    ///将字符串编码为写入文件的字节，有无法用该编码表示的字符时返回错误，避免丢失内容
+    pub fn encode(self, contents: &str) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        if self.bom {
+            bytes.extend_from_slice(self.bom_bytes());
+        }
+
+        //encoding_rs 不支持编码为 UTF-16，需要手动转换
+        if self.encoding == UTF_16LE {
+            bytes.extend(contents.encode_utf16().flat_map(u16::to_le_bytes));
+        } else if self.encoding == UTF_16BE {
+            bytes.extend(contents.encode_utf16().flat_map(u16::to_be_bytes));
+        } else {
+            let (encoded, _, had_errors) = self.encoding.encode(contents);
+            if had_errors {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("text cannot be represented in {self}"),
+                ));
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+        Ok(bytes)
+    }
+
+    fn bom_bytes(self) -> &'static [u8] {
+        if self.encoding == UTF_16LE {
+            UTF_16LE_BOM
+        } else if self.encoding == UTF_16BE {
+            UTF_16BE_BOM
+        } else {
+            UTF_8_BOM
+        }
+    }
+}
+
+impl Display for FileEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bom && self.encoding == UTF_8 {
+            write!(f, "{} BOM", self.encoding.name())
+        } else {
+            write!(f, "{}", self.encoding.name())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{GBK, WINDOWS_1252};
+
+    ///检测出的编码解码之后再编码，应当得到原来的字节
+    fn assert_round_trip(bytes: &[u8], expected: &str) {
+        let encoding = FileEncoding::detect(bytes, WINDOWS_1252);
+        let (contents, had_errors) = encoding.decode(bytes);
+        assert!(!had_errors);
+        assert_eq!(contents, expected);
+        assert_eq!(encoding.encode(&contents).unwrap(), bytes);
+    }
+
+    #[test]
+    fn detects_utf8_with_and_without_bom() {
+        assert_eq!(FileEncoding::detect("héllo".as_bytes(), WINDOWS_1252), FileEncoding::default());
+        assert_round_trip("héllo\n".as_bytes(), "héllo\n");
+        let with_bom = [UTF_8_BOM, "héllo".as_bytes()].concat();
+        assert_eq!(FileEncoding::detect(&with_bom, WINDOWS_1252).to_string(), "UTF-8 BOM");
+        assert_round_trip(&with_bom, "héllo");
+    }
+
+    #[test]
+    fn detects_utf16_by_bom() {
+        let little: Vec<u8> = [0xFF, 0xFE].into_iter().chain("a中".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        let big: Vec<u8> = [0xFE, 0xFF].into_iter().chain("a中".encode_utf16().flat_map(u16::to_be_bytes)).collect();
+        assert_round_trip(&little, "a中");
+        assert_round_trip(&big, "a中");
+    }
+
+    #[test]
+    fn falls_back_to_legacy_encoding_for_invalid_utf8() {
+        let bytes = [b'c', b'a', b'f', 0xE9];
+        assert_eq!(FileEncoding::detect(&bytes, WINDOWS_1252).to_string(), "windows-1252");
+        assert_round_trip(&bytes, "café");
+    }
+
+    #[test]
+    fn refuses_to_encode_unrepresentable_text() {
+        let encoding = FileEncoding::from_label("latin1").unwrap();
+        assert!(encoding.encode("中").is_err());
+        let gbk = FileEncoding::from_label("gbk").unwrap();
+        assert_eq!(gbk.decode(&gbk.encode("中文").unwrap()), (String::from("中文"), false));
+        assert_eq!(gbk.encoding, GBK);
+    }
+
+    #[test]
+    fn parses_labels() {
+        assert_eq!(FileEncoding::from_label("UTF-8-BOM").unwrap().to_string(), "UTF-8 BOM");
+        assert!(FileEncoding::from_label("utf-16le").unwrap().bom);
+        assert!(FileEncoding::from_label("latin1-bom").is_none());
+        assert!(FileEncoding::from_label("no-such-encoding").is_none());
+    }
+
+    #[test]
+    fn reports_bytes_that_cannot_be_decoded() {
+        let utf8 = FileEncoding::default();
+        assert_eq!(utf8.decode(b"caf\xe9"), (String::from("caf\u{FFFD}"), true));
+        let utf16 = FileEncoding::detect(&[0xFF, 0xFE, b'a', 0, 0x00, 0xD8], WINDOWS_1252);
+        assert!(utf16.decode(&[0xFF, 0xFE, b'a', 0, 0x00, 0xD8]).1);
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs::File;
+use std::io::{Error,Read};
+use std::path::{Path,PathBuf};
+use std::sync::mpsc::{self,Receiver,Sender,TryRecvError};
+use std::thread;
+use ignore::WalkBuilder;
+use crate::editor::size::Size;
+use super::{
+    terminal::Terminal,
+    uicomponent::UIComponent,
+    picker::Picker,
+    command::Move,
+    fuzzy,
+};
+
+const BATCH_SIZE: usize = 256; //后台线程每找到这么多文件发送一次
+const MAX_RESULTS: usize = 1000; //列表中最多显示的匹配数量
+const PREVIEW_BYTES: u64 = 16 * 1024; //预览时最多读取的字节数
+
+///Ctrl-P 打开的文件查找：后台线程遍历工作目录(跳过 .gitignore 忽略的文件)，按模糊匹配的得分排序
+/// 上半部分为匹配的文件列表，下半部分预览选中的文件
+pub struct FileFinder {
+    root: PathBuf, //遍历的起点，列表中的路径相对于它
+    receiver: Receiver<Vec<String>>, //后台线程找到的文件
+    walking: bool, //后台线程是否还在遍历
+    files: Vec<String>,
+    query: String,
+    matches: Vec<(i64, usize)>, //匹配的文件的得分以及在 files 中的位置，按得分从高到低排列
+    picker: Picker,
+    preview: Option<(usize, Vec<String>)>, //预览的文件在 files 中的位置以及其内容
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl FileFinder {
+    ///开始在后台遍历 root 下的文件
+    pub fn new(root: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let walk_root = root.clone();
+        thread::spawn(move || Self::walk(&walk_root, &sender));
+        let mut finder = Self {
+            root,
+            receiver,
+            walking: true,
+            files: Vec::new(),
+            query: String::new(),
+            matches: Vec::new(),
+            picker: Picker::default(),
+            preview: None,
+            needs_redraw: true,
+            size: Size::default(),
+        };
+        finder.update_picker();
+        finder
+    }
+
+    ///在后台线程中遍历，分批发送相对路径；查找被关闭(接收端被丢弃)时停止
+    fn walk(root: &Path, sender: &Sender<Vec<String>>) {
+        let mut batch = Vec::new();
+        for entry in WalkBuilder::new(root).require_git(false).build().flatten() {
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let path = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            batch.push(path.to_string_lossy().into_owned());
+            if batch.len() >= BATCH_SIZE && sender.send(std::mem::take(&mut batch)).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send(batch);
+    }
+
+    ///后台线程是否还在遍历，用于决定主循环等待输入的时间
+    pub const fn is_walking(&self) -> bool {
+        self.walking
+    }
+
+    ///接收后台线程新找到的文件，并按当前的输入计算得分
+    pub fn receive_files(&mut self) {
+        let mut received = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(batch) => {
+                    for file in batch {
+                        if let Some(score) = fuzzy::score(&self.query, &file) {
+                            self.matches.push((score, self.files.len()));
+                        }
+                        self.files.push(file);
+                    }
+                    received = true;
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.walking = false;
+                    received = true;
+                    break;
+                },
+            }
+        }
+        if received {
+            self.update_picker();
+        }
+    }
+
+    ///输入变化之后重新计算所有文件的得分
+    pub fn set_query(&mut self, query: &str) {
+        if query == self.query {
+            return;
+        }
+        self.query = query.to_string();
+        self.matches = self.files
+            .iter()
+            .enumerate()
+            .filter_map(|(index, file)| fuzzy::score(query, file).map(|score| (score, index)))
+            .collect();
+        self.update_picker();
+    }
+
+    ///排序匹配的文件并更新列表，得分相同时较短的路径在前
+    fn update_picker(&mut self) {
+        let files = &self.files;
+        self.matches.sort_by(|(score, index), (other_score, other_index)| {
+            other_score
+                .cmp(score)
+                .then_with(|| files[*index].len().cmp(&files[*other_index].len()))
+                .then_with(|| files[*index].cmp(&files[*other_index]))
+        });
+        let items = self.matches
+            .iter()
+            .take(MAX_RESULTS)
+            .map(|(_, index)| files[*index].clone())
+            .collect();
+        self.picker.set_items(items);
+        let scanning = if self.walking { ", scanning..." } else { "" };
+        self.picker.set_title(&format!("Find file: {} of {} files{scanning}", self.matches.len(), self.files.len()));
+        self.set_needs_redraw(true);
+    }
+
+    ///上下移动选中的文件
+    pub fn handle_move_command(&mut self, command: Move) {
+        self.picker.handle_move_command(command);
+        self.set_needs_redraw(true);
+    }
+
+    ///选中的文件的完整路径
+    pub fn selected(&self) -> Option<PathBuf> {
+        let (_, index) = self.matches.get(self.picker.selected())?;
+        Some(self.root.join(&self.files[*index]))
+    }
+
+    ///读取选中文件的开头用于预览，同一个文件只读取一次
+    fn update_preview(&mut self) {
+        let index = self.matches.get(self.picker.selected()).map(|(_, index)| *index);
+        if self.preview.as_ref().map(|(previewed, _)| *previewed) == index {
+            return;
+        }
+        self.preview = index.map(|index| (index, Self::read_preview(&self.root.join(&self.files[index]))));
+    }
+
+    ///文件开头的内容，二进制文件只给出提示
+    fn read_preview(path: &Path) -> Vec<String> {
+        let mut bytes = Vec::new();
+        if let Err(err) = File::open(path).and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut bytes)) {
+            return vec![format!("[could not read file: {err}]")];
+        }
+        if bytes.contains(&0) {
+            return vec![String::from("[binary file]")];
+        }
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect()
+    }
+}
+
+impl UIComponent for FileFinder {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        //列表占上半部分，其余用于预览
+        self.picker.resize(Size {
+            columns: size.columns,
+            rows: size.rows.div_ceil(2),
+        });
+    }
+
+    fn draw(&mut self, position_row: usize) -> Result<(), Error> {
+        let Size { columns, rows } = self.size;
+        let list_rows = rows.div_ceil(2);
+        self.picker.draw(position_row)?;
+
+        self.update_preview();
+        let preview_row = position_row.saturating_add(list_rows);
+        let preview_rows = rows.saturating_sub(list_rows);
+        if preview_rows == 0 {
+            return Ok(());
+        }
+        let title = self.preview.as_ref().map(|(index, _)| format!("Preview: {}", self.files[*index])).unwrap_or_default();
+        let title: String = title.chars().take(columns).collect();
+        Terminal::print_inverted_color_row(preview_row, &format!("{title:<columns$}"))?;
+
+        let lines = self.preview.as_ref().map(|(_, lines)| lines.as_slice()).unwrap_or_default();
+        for row in 1..preview_rows {
+            let text: String = lines
+                .get(row.saturating_sub(1))
+                .map(|line| line.chars().take(columns).collect())
+                .unwrap_or_default();
+            Terminal::print_row(preview_row.saturating_add(row), &text)?;
+        }
+        Ok(())
+    }
+}
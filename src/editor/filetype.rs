@@ -0,0 +1,82 @@
+use std::path::Path;
+
+///根据扩展名识别的文件类型，决定自动缩进等与语言相关的行为
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Default, Debug)]
+pub enum FileType {
+    Rust,
+    C, //c、c++、java、javascript 等使用花括号的语言
+    Python,
+    Shell,
+    Toml,
+    Markdown,
+    Html,
+    #[default]
+    Text,
+}
+
+impl FileType {
+    ///由文件路径的扩展名判断类型，没有路径或无法识别时为纯文本
+    pub fn from_path(path: Option<&Path>) -> Self {
+        let extension = path
+            .and_then(Path::extension)
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        match extension.as_str() {
+            "rs" => Self::Rust,
+            "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "js" | "ts" | "go" | "css" | "json" => Self::C,
+            "py" => Self::Python,
+            "sh" | "bash" | "zsh" => Self::Shell,
+            "toml" => Self::Toml,
+            "md" | "markdown" => Self::Markdown,
+            "html" | "htm" | "xml" => Self::Html,
+            _ => Self::Text,
+        }
+    }
+
+    ///设置中使用的名称，如 `autopairs.rust`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rust" => Some(Self::Rust),
+            "c" => Some(Self::C),
+            "python" => Some(Self::Python),
+            "shell" => Some(Self::Shell),
+            "toml" => Some(Self::Toml),
+            "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "text" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    ///行末是这些字符时，下一行多缩进一级
+    pub const fn indent_openers(self) -> &'static [char] {
+        match self {
+            Self::Rust | Self::C => &['{', '(', '['],
+            Self::Python => &[':', '{', '(', '['],
+            Self::Shell => &['{', '('],
+            Self::Toml | Self::Markdown | Self::Html | Self::Text => &[],
+        }
+    }
+
+    ///自动配对的开括号、引号以及对应的闭合符号
+    pub const fn auto_pairs(self) -> &'static [(char, char)] {
+        match self {
+            //Rust 的单引号多用于生命周期，文本中多为撇号，不自动配对
+            Self::Rust | Self::Markdown | Self::Text => &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"')],
+            Self::C | Self::Python | Self::Shell | Self::Toml | Self::Html => {
+                &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')]
+            },
+        }
+    }
+
+    ///注释的开头和结尾，行注释的结尾为空；纯文本没有注释
+    pub const fn comment_markers(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Rust | Self::C => Some(("//", "")),
+            Self::Python | Self::Shell | Self::Toml => Some(("#", "")),
+            Self::Markdown | Self::Html => Some(("<!--", "-->")),
+            Self::Text => None,
+        }
+    }
+}
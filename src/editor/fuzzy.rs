@@ -0,0 +1,71 @@
+const MATCH_SCORE: i64 = 1; //每个匹配的字符的基础得分
+const CONSECUTIVE_BONUS: i64 = 5; //紧跟在上一个匹配字符之后
+const BOUNDARY_BONUS: i64 = 8; //匹配在单词的开头，如 / _ - . 之后
+const FILE_NAME_BONUS: i64 = 3; //匹配在路径的最后一部分(文件名)中
+
+///模糊匹配：query 中的字符(忽略大小写)按顺序出现在 candidate 中即为匹配，不匹配时返回 None
+/// 连续的匹配、单词开头的匹配以及文件名中的匹配得分更高，较长的路径略微扣分
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let characters: Vec<char> = candidate.chars().collect();
+    let file_name_start = characters.iter().rposition(|&ch| ch == '/').map_or(0, |index| index.saturating_add(1));
+
+    let mut total: i64 = 0;
+    let mut previous: Option<usize> = None;
+    let mut start = 0;
+    for wanted in query.chars().filter(|ch| !ch.is_whitespace()) {
+        //从上一个匹配的位置向后寻找
+        let found = (start..characters.len())
+            .find(|&index| characters[index].to_lowercase().eq(wanted.to_lowercase()))?;
+
+        total = total.saturating_add(MATCH_SCORE);
+        if previous.is_some_and(|previous| previous.saturating_add(1) == found) {
+            total = total.saturating_add(CONSECUTIVE_BONUS);
+        }
+        if found == 0 || matches!(characters[found.saturating_sub(1)], '/' | '_' | '-' | '.' | ' ') {
+            total = total.saturating_add(BOUNDARY_BONUS);
+        }
+        if found >= file_name_start {
+            total = total.saturating_add(FILE_NAME_BONUS);
+        }
+        previous = Some(found);
+        start = found.saturating_add(1);
+    }
+
+    let length_penalty = i64::try_from(characters.len() / 8).unwrap_or(i64::MAX);
+    Some(total.saturating_sub(length_penalty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_must_appear_in_order() {
+        assert!(score("edr", "src/editor.rs").is_some());
+        assert!(score("EDR", "src/editor.rs").is_some());
+        assert!(score("rde", "src/editor.rs").is_none());
+        assert!(score("xyz", "src/editor.rs").is_none());
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(score("", "main.rs"), Some(0));
+        assert_eq!(score("  ", "main.rs"), Some(0));
+    }
+
+    #[test]
+    fn consecutive_and_boundary_matches_score_higher() {
+        assert!(score("view", "src/view.rs") > score("view", "src/xvxixexw.rs"));
+        assert!(score("br", "src/buffer_rs.txt") > score("br", "src/abxr.txt"));
+    }
+
+    #[test]
+    fn file_name_matches_beat_directory_matches() {
+        assert!(score("term", "src/terminal.rs") > score("term", "term/src/x.rs"));
+    }
+
+    #[test]
+    fn shorter_paths_win_ties() {
+        assert!(score("main", "main.rs") > score("main", "main.rs.backup.old.copy"));
+    }
+}
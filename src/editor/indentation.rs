@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use super::line::Line;
+
+///缩进的方式：Tab 或者若干个空格
+#[derive(Clone, Copy, Eq, PartialEq, Default, Debug)]
+pub enum Indentation {
+    #[default]
+    Tabs,
+    Spaces(usize), //每一级缩进的空格数
+}
+
+impl Indentation {
+    ///根据设置决定的缩进方式，用于无法从内容判断的文件
+    pub fn from_settings(expandtab: bool, width: usize) -> Self {
+        if expandtab { Self::Spaces(width.max(1)) } else { Self::Tabs }
+    }
+
+    ///统计文件中以 Tab 和以空格开头的行，取占多数的一种
+    /// 空格缩进的宽度取相邻两行缩进之差中最常见的值；没有缩进的行时返回 None
+    pub fn detect(lines: &[Line]) -> Option<Self> {
+        let mut tab_lines: usize = 0;
+        let mut space_lines: usize = 0;
+        let mut steps: HashMap<usize, usize> = HashMap::new();
+        let mut previous_spaces = 0;
+        for line in lines.iter().map(Line::to_string) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tab_lines = tab_lines.saturating_add(1);
+                continue;
+            }
+            let spaces = line.chars().take_while(|&ch| ch == ' ').count();
+            if spaces > 0 {
+                space_lines = space_lines.saturating_add(1);
+            }
+            let step = spaces.abs_diff(previous_spaces);
+            if (2..=8).contains(&step) {
+                let count = steps.entry(step).or_default();
+                *count = count.saturating_add(1);
+            }
+            previous_spaces = spaces;
+        }
+
+        if tab_lines == 0 && space_lines == 0 {
+            None
+        } else if tab_lines >= space_lines {
+            Some(Self::Tabs)
+        } else {
+            //出现次数相同时取较小的宽度
+            let width = steps
+                .into_iter()
+                .max_by(|(step, count), (other_step, other_count)| count.cmp(other_count).then(other_step.cmp(step)))
+                .map_or(4, |(step, _)| step);
+            Some(Self::Spaces(width))
+        }
+    }
+
+    ///一级缩进对应的文本
+    pub fn unit(self) -> String {
+        match self {
+            Self::Tabs => String::from("\t"),
+            Self::Spaces(width) => " ".repeat(width),
+        }
+    }
+}
+
+impl Display for Indentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tabs => write!(f, "tabs"),
+            Self::Spaces(width) => write!(f, "{width} spaces"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(contents: &str) -> Option<Indentation> {
+        let lines: Vec<Line> = contents.lines().map(Line::from).collect();
+        Indentation::detect(&lines)
+    }
+
+    #[test]
+    fn detects_the_most_common_space_step() {
+        assert_eq!(detect("a\n  b\n    c\n  d\n"), Some(Indentation::Spaces(2)));
+        assert_eq!(detect("a\n    b\n        c\n    d\ne\n    f\n"), Some(Indentation::Spaces(4)));
+    }
+
+    #[test]
+    fn majority_of_indented_lines_decides_between_tabs_and_spaces() {
+        assert_eq!(detect("a\n\tb\n\tc\n    d\n"), Some(Indentation::Tabs));
+        assert_eq!(detect("a\n\tb\n    c\n    d\n"), Some(Indentation::Spaces(4)));
+    }
+
+    #[test]
+    fn unindented_or_blank_files_are_undecided() {
+        assert_eq!(detect("a\nb\n"), None);
+        assert_eq!(detect("\n   \n"), None);
+        assert_eq!(detect(""), None);
+    }
+}
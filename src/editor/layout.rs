@@ -0,0 +1,361 @@
+use crate::editor::position::Position;
+use crate::editor::size::Size;
+use super::view::View;
+
+const MIN_PERCENT: usize = 10; //分屏时每一部分至少占的百分比
+const MAX_PERCENT: usize = 90;
+
+///分屏的方向
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Direction {
+    Horizontal, //上下排列
+    Vertical,   //左右排列
+}
+
+///屏幕上的一块矩形区域
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub origin: Position,
+    pub size: Size,
+}
+
+impl Rect {
+    const fn right(&self) -> usize {
+        self.origin.column.saturating_add(self.size.columns)
+    }
+
+    const fn bottom(&self) -> usize {
+        self.origin.row.saturating_add(self.size.rows)
+    }
+
+    ///在另一个方向上两块区域是否有重叠，用于寻找相邻的窗口
+    const fn overlaps(&self, other: &Self, direction: Direction) -> bool {
+        match direction {
+            Direction::Horizontal => self.origin.column < other.right() && other.origin.column < self.right(),
+            Direction::Vertical => self.origin.row < other.bottom() && other.origin.row < self.bottom(),
+        }
+    }
+}
+
+///分屏的布局树：叶子是窗口，内部节点将区域按比例分给两个子树
+/// 获得焦点的view保存在 Editor 中，它在树中的叶子为 None
+pub enum Layout {
+    Window(Option<View>),
+    Split {
+        direction: Direction,
+        percent: usize, //第一个子树占的百分比
+        first: Box<Layout>,
+        second: Box<Layout>,
+    },
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::Window(None)
+    }
+}
+
+impl Layout {
+    ///按照布局树划分区域，按叶子的顺序返回每个窗口的区域
+    /// 左右分屏时，两部分之间留出一列作为分隔线
+    pub fn rects(&self, area: Rect) -> Vec<Rect> {
+        match self {
+            Self::Window(_) => vec![area],
+            Self::Split { direction, percent, first, second } => {
+                let (first_area, second_area) = Self::divide(area, *direction, *percent);
+                let mut rects = first.rects(first_area);
+                rects.extend(second.rects(second_area));
+                rects
+            }
+        }
+    }
+
+    ///左右分屏时的分隔线所在的区域
+    pub fn separators(&self, area: Rect) -> Vec<Rect> {
+        match self {
+            Self::Window(_) => Vec::new(),
+            Self::Split { direction, percent, first, second } => {
+                let (first_area, second_area) = Self::divide(area, *direction, *percent);
+                let mut separators = Vec::new();
+                if *direction == Direction::Vertical {
+                    separators.push(Rect {
+                        origin: Position { column: first_area.right(), row: area.origin.row },
+                        size: Size { columns: 1, rows: area.size.rows },
+                    });
+                }
+                separators.extend(first.separators(first_area));
+                separators.extend(second.separators(second_area));
+                separators
+            }
+        }
+    }
+
+    fn divide(area: Rect, direction: Direction, percent: usize) -> (Rect, Rect) {
+        match direction {
+            Direction::Horizontal => {
+                let first_rows = area.size.rows.saturating_mul(percent) / 100;
+                let first = Rect {
+                    origin: area.origin,
+                    size: Size { columns: area.size.columns, rows: first_rows },
+                };
+                let second = Rect {
+                    origin: Position { column: area.origin.column, row: first.bottom() },
+                    size: Size { columns: area.size.columns, rows: area.size.rows.saturating_sub(first_rows) },
+                };
+                (first, second)
+            }
+            Direction::Vertical => {
+                let first_columns = area.size.columns.saturating_sub(1).saturating_mul(percent) / 100;
+                let first = Rect {
+                    origin: area.origin,
+                    size: Size { columns: first_columns, rows: area.size.rows },
+                };
+                let second = Rect {
+                    origin: Position { column: first.right().saturating_add(1), row: area.origin.row },
+                    size: Size {
+                        columns: area.size.columns.saturating_sub(first_columns).saturating_sub(1),
+                        rows: area.size.rows,
+                    },
+                };
+                (first, second)
+            }
+        }
+    }
+
+    ///窗口的数量
+    pub fn window_count(&self) -> usize {
+        match self {
+            Self::Window(_) => 1,
+            Self::Split { first, second, .. } => first.window_count().saturating_add(second.window_count()),
+        }
+    }
+
+    ///获得焦点的窗口是第几个叶子
+    pub fn focused_index(&self) -> usize {
+        self.windows().iter().position(Option::is_none).unwrap_or_default()
+    }
+
+    ///按叶子的顺序返回所有窗口，获得焦点的窗口为 None
+    pub fn windows(&self) -> Vec<Option<&View>> {
+        match self {
+            Self::Window(view) => vec![view.as_ref()],
+            Self::Split { first, second, .. } => {
+                let mut windows = first.windows();
+                windows.extend(second.windows());
+                windows
+            }
+        }
+    }
+
+    ///按叶子的顺序返回所有没有获得焦点的view
+    pub fn views_mut(&mut self) -> Vec<&mut View> {
+        match self {
+            Self::Window(view) => view.iter_mut().collect(),
+            Self::Split { first, second, .. } => {
+                let mut views = first.views_mut();
+                views.extend(second.views_mut());
+                views
+            }
+        }
+    }
+
+    ///将获得焦点的窗口一分为二，新的窗口显示 view，焦点留在原来的窗口
+    pub fn split_focused(&mut self, direction: Direction, view: View) {
+        match self {
+            Self::Window(None) => {
+                *self = Self::Split {
+                    direction,
+                    percent: 50,
+                    first: Box::new(Self::Window(None)),
+                    second: Box::new(Self::Window(Some(view))),
+                };
+            }
+            Self::Window(Some(_)) => {}
+            Self::Split { first, second, .. } => {
+                if first.contains_focus() {
+                    first.split_focused(direction, view);
+                } else {
+                    second.split_focused(direction, view);
+                }
+            }
+        }
+    }
+
+    ///关闭获得焦点的窗口，它的兄弟子树占据其位置；返回新获得焦点的view
+    /// 只剩一个窗口时不能关闭，返回 None
+    pub fn close_focused(&mut self) -> Option<View> {
+        let Self::Split { first, second, .. } = self else {
+            return None;
+        };
+        let sibling = if matches!(**first, Self::Window(None)) {
+            std::mem::take(&mut **second)
+        } else if matches!(**second, Self::Window(None)) {
+            std::mem::take(&mut **first)
+        } else if first.contains_focus() {
+            return first.close_focused();
+        } else {
+            return second.close_focused();
+        };
+        *self = sibling;
+        self.take_first()
+    }
+
+    ///取出第一个叶子的view，使其获得焦点
+    fn take_first(&mut self) -> Option<View> {
+        match self {
+            Self::Window(view) => view.take(),
+            Self::Split { first, .. } => first.take_first(),
+        }
+    }
+
+    ///将焦点移到第index个窗口：原来获得焦点的 view 放回树中，返回新获得焦点的view
+    /// index 需要小于窗口的数量
+    pub fn focus(&mut self, index: usize, focused: View) -> View {
+        let mut focused = Some(focused);
+        self.put_focused(&mut focused);
+        let mut counter = 0;
+        self.take_window(index, &mut counter).unwrap_or_default()
+    }
+
+    ///把 view 放回获得焦点的空叶子中
+    fn put_focused(&mut self, focused: &mut Option<View>) {
+        match self {
+            Self::Window(view @ None) => *view = focused.take(),
+            Self::Window(Some(_)) => {}
+            Self::Split { first, second, .. } => {
+                first.put_focused(focused);
+                second.put_focused(focused);
+            }
+        }
+    }
+
+    ///取出第index个叶子的view，该叶子变为获得焦点的空叶子
+    fn take_window(&mut self, index: usize, counter: &mut usize) -> Option<View> {
+        match self {
+            Self::Window(view) => {
+                let current = *counter;
+                *counter = counter.saturating_add(1);
+                if current == index { view.take() } else { None }
+            }
+            Self::Split { first, second, .. } => {
+                first.take_window(index, counter).or_else(|| second.take_window(index, counter))
+            }
+        }
+    }
+
+    fn contains_focus(&self) -> bool {
+        match self {
+            Self::Window(view) => view.is_none(),
+            Self::Split { first, second, .. } => first.contains_focus() || second.contains_focus(),
+        }
+    }
+
+    ///调整获得焦点的窗口所在的、最近的指定方向的分屏比例，delta 为正时焦点窗口变大
+    /// 没有该方向的分屏时返回 false
+    pub fn resize_focused(&mut self, direction: Direction, delta: isize) -> bool {
+        let Self::Split { direction: split_direction, percent, first, second } = self else {
+            return false;
+        };
+        let focus_in_first = first.contains_focus();
+        let child = if focus_in_first { first } else { second };
+        if child.resize_focused(direction, delta) {
+            return true;
+        }
+        if *split_direction != direction {
+            return false;
+        }
+        //焦点在第二部分时，增大焦点窗口意味着减小第一部分
+        let delta = if focus_in_first { delta } else { -delta };
+        *percent = percent.saturating_add_signed(delta).clamp(MIN_PERCENT, MAX_PERCENT);
+        true
+    }
+
+    ///在指定方向上与获得焦点的窗口相邻的窗口
+    /// forward 为 true 时向右或向下寻找，否则向左或向上
+    pub fn neighbour(&self, area: Rect, direction: Direction, forward: bool) -> Option<usize> {
+        let rects = self.rects(area);
+        let focused = rects.get(self.focused_index())?;
+        rects
+            .iter()
+            .enumerate()
+            .filter(|(_, rect)| rect.overlaps(focused, direction))
+            .filter_map(|(index, rect)| {
+                let distance = match (direction, forward) {
+                    (Direction::Horizontal, true) => rect.origin.row.checked_sub(focused.bottom()),
+                    (Direction::Horizontal, false) => focused.origin.row.checked_sub(rect.bottom()),
+                    (Direction::Vertical, true) => rect.origin.column.checked_sub(focused.right()),
+                    (Direction::Vertical, false) => focused.origin.column.checked_sub(rect.right()),
+                }?;
+                Some((distance, index))
+            })
+            .min()
+            .map(|(_, index)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(columns: usize, rows: usize) -> Rect {
+        Rect { origin: Position::default(), size: Size { columns, rows } }
+    }
+
+    fn bounds(rect: &Rect) -> (usize, usize, usize, usize) {
+        (rect.origin.column, rect.origin.row, rect.size.columns, rect.size.rows)
+    }
+
+    ///左边上下分屏、右边一个窗口，焦点在左上
+    fn three_windows() -> Layout {
+        let mut layout = Layout::default();
+        layout.split_focused(Direction::Vertical, View::default());
+        layout.split_focused(Direction::Horizontal, View::default());
+        layout
+    }
+
+    #[test]
+    fn single_window_takes_the_whole_area() {
+        let layout = Layout::default();
+        let rects: Vec<_> = layout.rects(area(80, 24)).iter().map(bounds).collect();
+        assert_eq!(rects, vec![(0, 0, 80, 24)]);
+        assert!(layout.separators(area(80, 24)).is_empty());
+    }
+
+    #[test]
+    fn vertical_split_leaves_a_separator_column() {
+        let layout = three_windows();
+        let rects: Vec<_> = layout.rects(area(81, 10)).iter().map(bounds).collect();
+        assert_eq!(rects, vec![(0, 0, 40, 5), (0, 5, 40, 5), (41, 0, 40, 10)]);
+        let separators: Vec<_> = layout.separators(area(81, 10)).iter().map(bounds).collect();
+        assert_eq!(separators, vec![(40, 0, 1, 10)]);
+    }
+
+    #[test]
+    fn closing_a_window_gives_its_area_to_the_sibling() {
+        let mut layout = three_windows();
+        assert_eq!(layout.window_count(), 3);
+        assert!(layout.close_focused().is_some());
+        assert_eq!(layout.window_count(), 2);
+        assert_eq!(layout.focused_index(), 0);
+        let rects: Vec<_> = layout.rects(area(81, 10)).iter().map(bounds).collect();
+        assert_eq!(rects, vec![(0, 0, 40, 10), (41, 0, 40, 10)]);
+        assert!(layout.close_focused().is_some());
+        assert_eq!(layout.window_count(), 1);
+        assert!(layout.close_focused().is_none());
+    }
+
+    #[test]
+    fn neighbour_finds_the_adjacent_window() {
+        let mut layout = three_windows();
+        let area = area(81, 10);
+        assert_eq!(layout.neighbour(area, Direction::Vertical, true), Some(2));
+        assert_eq!(layout.neighbour(area, Direction::Horizontal, true), Some(1));
+        assert_eq!(layout.neighbour(area, Direction::Vertical, false), None);
+        assert_eq!(layout.neighbour(area, Direction::Horizontal, false), None);
+
+        let _ = layout.focus(2, View::default());
+        assert_eq!(layout.focused_index(), 2);
+        assert_eq!(layout.neighbour(area, Direction::Vertical, false), Some(0));
+        assert_eq!(layout.neighbour(area, Direction::Vertical, true), None);
+    }
+}
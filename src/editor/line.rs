@@ -3,6 +3,8 @@ use std::{fmt, ops::Range};
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+pub const TAB_WIDTH: usize = 4; //Tab 显示时对齐到的列宽
+
 #[derive(Clone, Copy)]
 enum GraphemeWidth {
     Half,
@@ -101,7 +103,7 @@ impl Line {
             //计算当前grapheme的渲染宽度
             let fragment_width =  if fragment.grapheme == "\t" {
                 //默认Tab占4个字符，键入Tab键会移动到mod 4 为0的地方去
-                4 - (current_pos % 4)
+                TAB_WIDTH - (current_pos % TAB_WIDTH)
             } else {
                 //处理非Tab的渲染长度
                 match fragment.rendered_width {
@@ -155,7 +157,7 @@ impl Line {
         let mut width = 0;
         for fragment in self.fragments.iter().take(grapheme_index) {
             let fragment_width = if fragment.grapheme == "\t" {//对于tab键，会占据可变的长度
-                4 - (width % 4)
+                TAB_WIDTH - (width % TAB_WIDTH)
             } else {
                 match fragment.rendered_width {
                     GraphemeWidth::Zero => 0,
@@ -223,6 +225,11 @@ impl Line {
 
     }
 
+    ///第几个字素，超出范围时为 None
+    pub fn grapheme_at(&self,grapheme_index: usize) -> Option<&str> {
+        self.fragments.get(grapheme_index).map(|fragment| fragment.grapheme.as_str())
+    }
+
     ///删除最后一个字符
     pub fn delete_last(&mut self) {
         self.delete(self.grapheme_count().saturating_sub(1));
@@ -236,6 +243,35 @@ impl Line {
         }
     }
 
+    ///将所有的Tab替换为空格，每个字符显示的列保持不变
+    pub fn expand_tabs(&self) -> Self {
+        let mut result = String::new();
+        for (index, fragment) in self.fragments.iter().enumerate() {
+            if fragment.grapheme == "\t" {
+                let width = self.width_until(index.saturating_add(1)).saturating_sub(self.width_until(index));
+                result.push_str(&" ".repeat(width));
+            } else {
+                result.push_str(&fragment.grapheme);
+            }
+        }
+        Self::from(&result)
+    }
+
+    ///将行首的空白转换为尽可能多的Tab，不足一个Tab宽度的部分保留为空格，其余内容不变
+    pub fn tabify_indent(&self) -> Self {
+        let indent = self.fragments
+            .iter()
+            .take_while(|fragment| fragment.grapheme == " " || fragment.grapheme == "\t")
+            .count();
+        let width = self.width_until(indent);
+        let mut result = "\t".repeat(width / TAB_WIDTH);
+        result.push_str(&" ".repeat(width % TAB_WIDTH));
+        for fragment in self.fragments.iter().skip(indent) {
+            result.push_str(&fragment.grapheme);
+        }
+        Self::from(&result)
+    }
+
     ///在Line后面追加另一个Line的内容
     pub fn append(&mut self,other:&Self) {
         let mut concat = self.to_string(); //当前Line的字符内容
@@ -263,3 +299,24 @@ impl fmt::Display for Line  {
         write!(f, "{result}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_tabs_keeps_alignment() {
+        assert_eq!(Line::from("\tfoo").expand_tabs().to_string(), "    foo");
+        assert_eq!(Line::from("ab\tc").expand_tabs().to_string(), "ab  c");
+        assert_eq!(Line::from("  \t\tx").expand_tabs().to_string(), "        x");
+        assert_eq!(Line::from("中\tx").expand_tabs().to_string(), "中  x");
+    }
+
+    #[test]
+    fn tabify_indent_only_converts_leading_whitespace() {
+        assert_eq!(Line::from("        foo").tabify_indent().to_string(), "\t\tfoo");
+        assert_eq!(Line::from("      foo  bar").tabify_indent().to_string(), "\t  foo  bar");
+        assert_eq!(Line::from("  \tfoo").tabify_indent().to_string(), "\tfoo");
+        assert_eq!(Line::from("foo    ").tabify_indent().to_string(), "foo    ");
+    }
+}
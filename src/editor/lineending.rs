@@ -0,0 +1,106 @@
+use std::fmt::{self, Display};
+
+///一行末尾的换行符
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum LineEnding {
+    #[default]
+    Lf, // \n
+    Crlf, // \r\n
+}
+
+impl LineEnding {
+    ///换行符对应的字符串
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+
+    ///将字符串末尾的换行符拆分出来，没有换行符时返回None
+    pub fn split(line: &str) -> (&str, Option<Self>) {
+        if let Some(text) = line.strip_suffix("\r\n") {
+            (text, Some(Self::Crlf))
+        } else if let Some(text) = line.strip_suffix('\n') {
+            (text, Some(Self::Lf))
+        } else {
+            (line, None)
+        }
+    }
+}
+
+impl TryFrom<&str> for LineEnding {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "lf" | "unix" => Ok(Self::Lf),
+            "crlf" | "dos" => Ok(Self::Crlf),
+            _ => Err(format!("unknown line ending: {value}")),
+        }
+    }
+}
+
+///整个文件的换行风格
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum LineEndingStyle {
+    #[default]
+    Lf,
+    Crlf,
+    Mixed, //同时存在 LF 和 CRLF
+}
+
+impl LineEndingStyle {
+    ///根据每一行的换行符判断文件的换行风格，没有行时视为 LF
+    pub fn detect(endings: &[LineEnding]) -> Self {
+        let crlf_count = endings.iter().filter(|ending| **ending == LineEnding::Crlf).count();
+        if crlf_count == 0 {
+            Self::Lf
+        } else if crlf_count == endings.len() {
+            Self::Crlf
+        } else {
+            Self::Mixed
+        }
+    }
+}
+
+impl Display for LineEndingStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Mixed => "Mixed",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_separates_line_endings() {
+        assert_eq!(LineEnding::split("text\r\n"), ("text", Some(LineEnding::Crlf)));
+        assert_eq!(LineEnding::split("text\n"), ("text", Some(LineEnding::Lf)));
+        assert_eq!(LineEnding::split("text"), ("text", None));
+        //单独的 \r 不是换行符
+        assert_eq!(LineEnding::split("text\r"), ("text\r", None));
+        assert_eq!(LineEnding::split("\r\n"), ("", Some(LineEnding::Crlf)));
+    }
+
+    #[test]
+    fn detect_style_from_line_endings() {
+        assert_eq!(LineEndingStyle::detect(&[]), LineEndingStyle::Lf);
+        assert_eq!(LineEndingStyle::detect(&[LineEnding::Lf, LineEnding::Lf]), LineEndingStyle::Lf);
+        assert_eq!(LineEndingStyle::detect(&[LineEnding::Crlf, LineEnding::Crlf]), LineEndingStyle::Crlf);
+        assert_eq!(LineEndingStyle::detect(&[LineEnding::Crlf, LineEnding::Lf]), LineEndingStyle::Mixed);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(LineEnding::try_from("CRLF"), Ok(LineEnding::Crlf));
+        assert_eq!(LineEnding::try_from("unix"), Ok(LineEnding::Lf));
+        assert!(LineEnding::try_from("cr").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use super::command::Move;
+use super::encoding::FileEncoding;
+use super::layout::Direction;
+use super::lineending::LineEnding;
+
+///在命令提示栏(Ctrl-E)中输入的指令，例如 `eol crlf`
+pub enum NamedCommand {
+    LineEnding(LineEnding), //eol lf|crlf，转换整个文件的换行符
+    Reopen(FileEncoding),   //reopen <编码>，以指定编码重新读取文件
+    Encoding(FileEncoding), //encoding <编码>，之后以指定编码保存文件
+    DiffBackup,             //diffbackup，比较当前内容与最新的备份
+    RestoreBackup,          //restorebackup，用最新的备份替换当前内容
+    Set(String, String),    //set <key>=<value>，修改一项设置；set <key> / set no<key> 打开或关闭开关
+    Open(String),           //open <文件>，在新的buffer中打开文件
+    NextBuffer,             //bnext，切换到下一个buffer
+    PreviousBuffer,         //bprev，切换到上一个buffer
+    ListBuffers,            //buffers，列出所有打开的buffer
+    CloseBuffer(bool),      //bclose，关闭当前buffer；bclose! 放弃未保存的修改
+    Split(Direction),       //split / vsplit，上下或左右分屏
+    CloseWindow,            //close，关闭当前窗口
+    ResizeWindow(Direction, isize), //resize / vresize +N|-N，调整当前窗口的高度或宽度(百分比)
+    Focus(Move),            //focus up|down|left|right，将焦点移到相邻的窗口
+    Grep(String, bool),     //grep <文本> / egrep <正则表达式>，在工作目录下的所有文件中搜索
+    Revert,                 //revert，放弃未保存的修改，重新读取磁盘上的文件
+    Retab(bool),            //retab spaces|tabs，整个文件在Tab与空格之间转换，true 表示转为空格
+    UndoSave,               //undosave，撤销最近一次保存时对内容的整理
+}
+
+impl TryFrom<&str> for NamedCommand {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut words = value.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next().unwrap_or_default();
+        //名称之后的全部内容，用于可能包含空格的文件名
+        let rest = value.trim().split_once(char::is_whitespace).map_or("", |(_, rest)| rest.trim());
+
+        match name {
+            "eol" => LineEnding::try_from(argument).map(Self::LineEnding),
+            "reopen" => Self::parse_encoding(argument).map(Self::Reopen),
+            "encoding" => Self::parse_encoding(argument).map(Self::Encoding),
+            "diffbackup" => Ok(Self::DiffBackup),
+            "restorebackup" => Ok(Self::RestoreBackup),
+            "set" => Self::parse_setting(argument, words.next()),
+            "open" | "e" if rest.is_empty() => Err(String::from("usage: open <file>")),
+            "open" | "e" => Ok(Self::Open(rest.to_string())),
+            "bnext" | "bn" => Ok(Self::NextBuffer),
+            "bprev" | "bp" => Ok(Self::PreviousBuffer),
+            "buffers" | "ls" => Ok(Self::ListBuffers),
+            "bclose" | "bd" => Ok(Self::CloseBuffer(false)),
+            "bclose!" | "bd!" => Ok(Self::CloseBuffer(true)),
+            "split" | "sp" => Ok(Self::Split(Direction::Horizontal)),
+            "vsplit" | "vs" => Ok(Self::Split(Direction::Vertical)),
+            "close" => Ok(Self::CloseWindow),
+            "resize" => Self::parse_delta(argument).map(|delta| Self::ResizeWindow(Direction::Horizontal, delta)),
+            "vresize" => Self::parse_delta(argument).map(|delta| Self::ResizeWindow(Direction::Vertical, delta)),
+            "focus" => match argument {
+                "up" => Ok(Self::Focus(Move::Up)),
+                "down" => Ok(Self::Focus(Move::Down)),
+                "left" => Ok(Self::Focus(Move::Left)),
+                "right" => Ok(Self::Focus(Move::Right)),
+                _ => Err(String::from("usage: focus up|down|left|right")),
+            },
+            "revert" => Ok(Self::Revert),
+            "undosave" => Ok(Self::UndoSave),
+            "retab" => match argument {
+                "spaces" => Ok(Self::Retab(true)),
+                "tabs" => Ok(Self::Retab(false)),
+                _ => Err(String::from("usage: retab spaces|tabs")),
+            },
+            "grep" | "egrep" if rest.is_empty() => Err(format!("usage: {name} <pattern>")),
+            "grep" => Ok(Self::Grep(rest.to_string(), false)),
+            "egrep" => Ok(Self::Grep(rest.to_string(), true)),
+            "" => Err(String::from("no command given")),
+            _ => Err(format!("unknown command: {name}")),
+        }
+    }
+}
+
+impl NamedCommand {
+    fn parse_encoding(label: &str) -> Result<FileEncoding, String> {
+        FileEncoding::from_label(label).ok_or_else(|| format!("unknown encoding: {label}"))
+    }
+
+    ///窗口大小的变化量，如 +10、-5
+    fn parse_delta(argument: &str) -> Result<isize, String> {
+        argument
+            .strip_prefix('+')
+            .unwrap_or(argument)
+            .parse()
+            .map_err(|_| String::from("usage: resize +N|-N"))
+    }
+
+    ///支持 `set key=value`、`set key value`、`set key`(打开) 以及 `set nokey`(关闭)
+    fn parse_setting(argument: &str, value: Option<&str>) -> Result<Self, String> {
+        if argument.is_empty() {
+            return Err(String::from("usage: set <key>=<value>"));
+        }
+        let (key, value) = if let Some((key, value)) = argument.split_once('=') {
+            (key, value)
+        } else if let Some(value) = value {
+            (argument, value)
+        } else if let Some(key) = argument.strip_prefix("no") {
+            (key, "off")
+        } else {
+            (argument, "on")
+        };
+        Ok(Self::Set(key.trim().to_string(), value.trim().to_string()))
+    }
+}
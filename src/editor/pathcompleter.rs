@@ -0,0 +1,227 @@
+use std::{
+    env,
+    fs,
+    path::{Path, PathBuf},
+};
+
+///路径补全器，用于"Save as:"等需要输入文件路径的提示栏
+/// 相对路径以 `base_dir` 为基准(通常是当前文件所在的目录)，为 None 时以当前工作目录为基准
+#[derive(Default)]
+pub struct PathCompleter {
+    base_dir: Option<PathBuf>,
+    last_was_tab: bool, //上一次按键是否为 Tab，用于判断第二次 Tab 时列出候选项
+}
+
+///一次补全的结果
+pub struct Completion {
+    pub value: String,           //补全后的输入
+    pub candidates: Vec<String>, //需要展示给用户的候选项，为空表示不展示
+}
+
+impl PathCompleter {
+    pub fn new(base_dir: Option<PathBuf>) -> Self {
+        Self {
+            base_dir,
+            last_was_tab: false,
+        }
+    }
+
+    ///非 Tab 按键会打断连续的 Tab
+    pub fn reset(&mut self) {
+        self.last_was_tab = false;
+    }
+
+    ///对输入进行补全：第一次 Tab 补全到所有候选项的公共前缀，若无法继续补全，第二次 Tab 列出候选项
+    pub fn complete(&mut self, input: &str) -> Completion {
+        let expanded = expand(input);
+        let (dir_part, prefix) = match expanded.rfind('/') {
+            Some(index) => expanded.split_at(index.saturating_add(1)),
+            None => ("", expanded.as_str()),
+        };
+
+        let search_dir = resolve(dir_part, self.base_dir.as_deref());
+        let matches = list_matches(&search_dir, prefix);
+
+        let completed_name = match matches.as_slice() {
+            [] => prefix.to_string(),
+            [only] => only.clone(),
+            _ => common_prefix(&matches),
+        };
+        let value = format!("{dir_part}{completed_name}");
+
+        let candidates = if value == input && self.last_was_tab && matches.len() > 1 {
+            matches
+        } else {
+            Vec::new()
+        };
+        self.last_was_tab = true;
+
+        Completion { value, candidates }
+    }
+}
+
+///展开路径开头的 `~` 以及其中的环境变量 `$VAR`、`${VAR}`；不存在的变量保持原样
+pub fn expand(input: &str) -> String {
+    let mut result = String::new();
+    let mut rest = input;
+
+    if (rest == "~" || rest.starts_with("~/"))
+        && let Some(home) = env::var_os("HOME")
+    {
+        result.push_str(&home.to_string_lossy());
+        rest = &rest[1..];
+    }
+
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        let after = &rest[index.saturating_add(1)..];
+        let (name, remaining) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end.saturating_add(1)..]),
+                None => ("", after),
+            }
+        } else {
+            let end = after
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => result.push_str(&value),
+            _ => result.push_str(&rest[index..rest.len().saturating_sub(remaining.len())]),
+        }
+        rest = remaining;
+    }
+    result.push_str(rest);
+    result
+}
+
+///将用户输入的路径解析为实际路径：先展开，再将相对路径拼接到 `base_dir` 上
+pub fn resolve(input: &str, base_dir: Option<&Path>) -> PathBuf {
+    let expanded = PathBuf::from(expand(input));
+    match base_dir {
+        Some(base) if expanded.is_relative() => base.join(expanded),
+        _ => expanded,
+    }
+}
+
+///列出目录中以 `prefix` 开头的条目，目录名以 `/` 结尾；隐藏文件只有在 `prefix` 以 `.` 开头时才列出
+fn list_matches(dir: &Path, prefix: &str) -> Vec<String> {
+    let search_dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = fs::read_dir(search_dir) else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            //跟随符号链接判断是否为目录
+            if entry.path().is_dir() {
+                Some(format!("{name}/"))
+            } else {
+                Some(name)
+            }
+        })
+        .collect();
+    matches.sort();
+    matches
+}
+
+///所有候选项的最长公共前缀，以字符为单位比较
+fn common_prefix(matches: &[String]) -> String {
+    let Some(first) = matches.first() else {
+        return String::new();
+    };
+    let mut prefix_len = first.len();
+    for other in &matches[1..] {
+        prefix_len = first
+            .char_indices()
+            .zip(other.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((index, ch), _)| index.saturating_add(ch.len_utf8()))
+            .min(prefix_len);
+    }
+    first[..prefix_len].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::testdir::TestDir;
+
+
+    #[test]
+    fn completes_to_common_prefix_then_lists_candidates() {
+        let dir = TestDir::new("pathcompleter-prefix");
+        fs::write(dir.join("alpha.txt"), "").unwrap();
+        fs::write(dir.join("alphabet.txt"), "").unwrap();
+        let mut completer = PathCompleter::new(Some(dir.to_path_buf()));
+
+        let first = completer.complete("al");
+        assert_eq!(first.value, "alpha");
+        assert!(first.candidates.is_empty());
+
+        let second = completer.complete("alpha");
+        assert_eq!(second.value, "alpha");
+        assert_eq!(second.candidates, vec!["alpha.txt", "alphabet.txt"]);
+    }
+
+    #[test]
+    fn completes_directories_with_slash_and_skips_hidden_files() {
+        let dir = TestDir::new("pathcompleter-dirs");
+        fs::create_dir(dir.join("beta")).unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+        let mut completer = PathCompleter::new(Some(dir.to_path_buf()));
+
+        assert_eq!(completer.complete("b").value, "beta/");
+        assert_eq!(list_matches(&dir, ""), vec!["beta/"]);
+        assert_eq!(list_matches(&dir, "."), vec![".hidden"]);
+    }
+
+    #[test]
+    fn tab_count_resets_after_other_keys() {
+        let dir = TestDir::new("pathcompleter-reset");
+        fs::write(dir.join("one"), "").unwrap();
+        fs::write(dir.join("other"), "").unwrap();
+        let mut completer = PathCompleter::new(Some(dir.to_path_buf()));
+
+        assert_eq!(completer.complete("o").value, "o");
+        completer.reset();
+        assert!(completer.complete("o").candidates.is_empty());
+        assert_eq!(completer.complete("o").candidates, vec!["one", "other"]);
+    }
+
+    #[test]
+    fn expands_home_and_keeps_unknown_variables() {
+        let home = env::var("HOME").unwrap();
+        assert_eq!(expand("~/notes"), format!("{home}/notes"));
+        assert_eq!(expand("$HOME/a"), format!("{home}/a"));
+        assert_eq!(expand("${HOME}/a"), format!("{home}/a"));
+        assert_eq!(expand("$HECTO_NO_SUCH_VARIABLE/a"), "$HECTO_NO_SUCH_VARIABLE/a");
+        assert_eq!(expand("a~b"), "a~b");
+    }
+
+    #[test]
+    fn resolves_relative_paths_against_base_dir() {
+        assert_eq!(resolve("x.txt", Some(Path::new("/tmp/base"))), PathBuf::from("/tmp/base/x.txt"));
+        assert_eq!(resolve("/abs/x.txt", Some(Path::new("/tmp/base"))), PathBuf::from("/abs/x.txt"));
+        assert_eq!(resolve("x.txt", None), PathBuf::from("x.txt"));
+    }
+
+    #[test]
+    fn common_prefix_respects_char_boundaries() {
+        assert_eq!(common_prefix(&[String::from("日本語"), String::from("日本人")]), "日本");
+        assert_eq!(common_prefix(&[String::from("abc"), String::from("xyz")]), "");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
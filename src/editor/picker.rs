@@ -0,0 +1,127 @@
+use std::io::Error;
+use crate::editor::size::Size;
+use super::{
+    terminal::Terminal,
+    uicomponent::UIComponent,
+    command::Move,
+};
+
+///在view的位置显示一个可以上下选择的列表，如打开的buffer
+/// 第一行为标题，选中的一项反色显示
+#[derive(Default)]
+pub struct Picker {
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+    scroll_offset: usize, //列表从第几项开始显示
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl Picker {
+    pub fn new(title: &str, items: Vec<String>, selected: usize) -> Self {
+        let mut picker = Self {
+            title: title.to_string(),
+            items,
+            ..Self::default()
+        };
+        picker.select(selected);
+        picker
+    }
+
+    ///当前选中的项
+    pub const fn selected(&self) -> usize {
+        self.selected
+    }
+
+    ///替换标题，如显示匹配的数量
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.set_needs_redraw(true);
+    }
+
+    ///替换列表内容，选中项保持在合法范围内
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.select(self.selected);
+    }
+
+    ///上下移动选中项，其他移动指令按翻页处理
+    pub fn handle_move_command(&mut self, command: Move) {
+        let page = self.visible_rows().saturating_sub(1).max(1);
+        let selected = match command {
+            Move::Up | Move::Left => self.selected.saturating_sub(1),
+            Move::Down | Move::Right => self.selected.saturating_add(1),
+            Move::Pageup => self.selected.saturating_sub(page),
+            Move::PageDown => self.selected.saturating_add(page),
+            Move::StartofLine => 0,
+            Move::EndofLine => self.items.len(),
+            Move::MatchingBracket => self.selected,
+        };
+        self.select(selected);
+    }
+
+    ///选中指定的项，并滚动到可见的范围
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+        let rows = self.visible_rows();
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if rows > 0 && self.selected >= self.scroll_offset.saturating_add(rows) {
+            self.scroll_offset = self.selected.saturating_sub(rows).saturating_add(1);
+        }
+        self.set_needs_redraw(true);
+    }
+
+    ///除去标题之后可以显示的行数
+    const fn visible_rows(&self) -> usize {
+        self.size.rows.saturating_sub(1)
+    }
+
+    ///选中项在组件中所在的行，用于放置光标
+    pub const fn caret_row(&self) -> usize {
+        self.selected.saturating_sub(self.scroll_offset).saturating_add(1)
+    }
+}
+
+impl UIComponent for Picker {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.select(self.selected);
+    }
+
+    fn draw(&mut self, position_row: usize) -> Result<(), Error> {
+        let Size { columns, rows } = self.size;
+        if rows == 0 {
+            return Ok(());
+        }
+
+        let title: String = self.title.chars().take(columns).collect();
+        Terminal::print_inverted_color_row(position_row, &format!("{title:<columns$}"))?;
+
+        for row in 1..rows {
+            let index = self.scroll_offset.saturating_add(row).saturating_sub(1);
+            let Some(item) = self.items.get(index) else {
+                Terminal::print_row(position_row.saturating_add(row), "~")?;
+                continue;
+            };
+            let mut text: String = item.chars().take(columns).collect();
+            if index == self.selected {
+                //反色填满整行，便于看清选中项
+                text = format!("{text:<columns$}");
+                Terminal::print_inverted_color_row(position_row.saturating_add(row), &text)?;
+            } else {
+                Terminal::print_row(position_row.saturating_add(row), &text)?;
+            }
+        }
+        Ok(())
+    }
+}
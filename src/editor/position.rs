@@ -7,6 +7,13 @@ pub struct Position {
 
 
 impl Position {
+    pub const fn saturating_add(self,other:Self) -> Self {
+        Self {
+            column: self.column.saturating_add(other.column),
+            row: self.row.saturating_add(other.row)
+        }
+    }
+
     pub const fn saturating_sub(self,other:Self) -> Self {
         Self {
             column: self.column.saturating_sub(other.column),
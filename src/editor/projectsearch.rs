@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path,PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool,Ordering};
+use std::sync::mpsc::{self,Receiver,Sender,TryRecvError};
+use std::thread;
+use encoding_rs::Encoding;
+use ignore::WalkBuilder;
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+use super::encoding::FileEncoding;
+use super::view::Location;
+
+const BINARY_CHECK_BYTES: usize = 8000; //检查文件开头的这些字节中是否有 NUL，有则视为二进制文件
+const MAX_MATCHES: usize = 10_000; //最多找到的匹配数量，超过时停止搜索
+const SNIPPET_CHARS: usize = 200; //每个匹配显示的上下文最多的字符数
+
+///搜索到的一处匹配
+pub struct SearchMatch {
+    pub path: PathBuf,
+    pub location: Location, //匹配在文件中的行和字素位置
+    pub text: String, //显示在结果列表中的一行，path:line:col: 上下文
+}
+
+///在后台线程中搜索工作目录下的所有文件(跳过二进制文件以及 .gitignore 忽略的文件)
+/// 结果分批通过 channel 发送，丢弃或取消之后后台线程尽快停止
+pub struct ProjectSearch {
+    receiver: Receiver<Vec<SearchMatch>>,
+    cancelled: Arc<AtomicBool>,
+    running: bool,
+    count: usize, //已经收到的匹配数量
+}
+
+impl ProjectSearch {
+    ///开始搜索，regex 为 false 时按字面文本搜索；正则表达式不合法时返回错误信息
+    /// 文件的编码与打开文件时一样判断，既不是 UTF-8 也没有 BOM 时按 `legacy_encoding` 解码
+    pub fn start(root: PathBuf, pattern: &str, regex: bool, legacy_encoding: &'static Encoding) -> Result<Self, String> {
+        let pattern = if regex { pattern.to_string() } else { regex::escape(pattern) };
+        let expression = Regex::new(&pattern).map_err(|err| format!("invalid pattern: {err}"))?;
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancelled);
+        thread::spawn(move || Self::search(&root, &expression, legacy_encoding, &flag, &sender));
+        Ok(Self {
+            receiver,
+            cancelled,
+            running: true,
+            count: 0,
+        })
+    }
+
+    ///在后台线程中逐个文件搜索，每个文件的匹配作为一批发送
+    fn search(root: &Path, expression: &Regex, legacy_encoding: &'static Encoding, cancelled: &AtomicBool, sender: &Sender<Vec<SearchMatch>>) {
+        let mut total: usize = 0;
+        for entry in WalkBuilder::new(root).require_git(false).build().flatten() {
+            if cancelled.load(Ordering::Relaxed) || total >= MAX_MATCHES {
+                return;
+            }
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let Ok(bytes) = fs::read(entry.path()) else {
+                continue;
+            };
+            //UTF-16 的文件中有 NUL，带有 BOM 时不视为二进制文件
+            if Encoding::for_bom(&bytes).is_none() && bytes.iter().take(BINARY_CHECK_BYTES).any(|&byte| byte == 0) {
+                continue;
+            }
+            let (contents, _) = FileEncoding::detect(&bytes, legacy_encoding).decode(&bytes);
+            let matches = Self::search_file(root, entry.path(), &contents, expression);
+            total = total.saturating_add(matches.len());
+            if !matches.is_empty() && sender.send(matches).is_err() {
+                return;
+            }
+        }
+    }
+
+    ///一个文件中的所有匹配，列号按字素计算，与 `Location` 一致；列表中显示相对于 root 的路径
+    fn search_file(root: &Path, path: &Path, contents: &str, expression: &Regex) -> Vec<SearchMatch> {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let mut matches = Vec::new();
+        for (line_index, line) in contents.lines().enumerate() {
+            for found in expression.find_iter(line) {
+                let grapheme_index = line[..found.start()].graphemes(true).count();
+                let snippet: String = line.trim().chars().take(SNIPPET_CHARS).collect();
+                matches.push(SearchMatch {
+                    path: path.to_path_buf(),
+                    location: Location { grapheme_index, line_index },
+                    text: format!(
+                        "{}:{}:{}: {snippet}",
+                        relative.display(),
+                        line_index.saturating_add(1),
+                        grapheme_index.saturating_add(1),
+                    ),
+                });
+            }
+        }
+        matches
+    }
+
+    ///接收后台线程新找到的匹配，搜索结束后 `is_running` 返回 false
+    pub fn receive(&mut self) -> Vec<SearchMatch> {
+        let mut received = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(batch) => received.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.running = false;
+                    break;
+                },
+            }
+        }
+        self.count = self.count.saturating_add(received.len());
+        received
+    }
+
+    ///通知后台线程停止搜索
+    pub fn cancel(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.running = false;
+    }
+
+    pub const fn is_running(&self) -> bool {
+        self.running
+    }
+
+    ///已经找到的匹配数量
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    ///是否因为匹配太多而提前停止
+    pub const fn is_truncated(&self) -> bool {
+        self.count >= MAX_MATCHES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::testdir::TestDir;
+    use encoding_rs::WINDOWS_1252;
+
+    fn collect(search: &mut ProjectSearch) -> Vec<String> {
+        let mut texts = Vec::new();
+        while search.is_running() {
+            texts.extend(search.receive().into_iter().map(|found| found.text));
+            thread::yield_now();
+        }
+        texts.sort();
+        texts
+    }
+
+    #[test]
+    fn decodes_files_like_the_editor_does() {
+        let root = TestDir::new("projectsearch-encoding");
+        fs::write(root.join("latin.txt"), b"un caf\xe9 noir\n").unwrap();
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain("café crème\n".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        fs::write(root.join("wide.txt"), utf16).unwrap();
+        fs::write(root.join("binary.bin"), b"caf\xc3\xa9\0").unwrap();
+
+        let mut search = ProjectSearch::start(root.to_path_buf(), "café", false, WINDOWS_1252).unwrap();
+        assert_eq!(collect(&mut search), vec!["latin.txt:1:4: un café noir", "wide.txt:1:1: café crème"]);
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(ProjectSearch::start(PathBuf::from("."), "(", true, WINDOWS_1252).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use encoding_rs::{Encoding, WINDOWS_1252};
+use super::pathcompleter;
+use super::filetype::FileType;
+use std::{collections::HashMap, env, fs, path::PathBuf, time::Duration};
+
+///保存前备份磁盘上原有内容的方式
+#[derive(Clone, Copy, Eq, PartialEq, Default)]
+pub enum BackupMode {
+    #[default]
+    Off,
+    Local,   //备份在文件旁边，如 `file~`
+    Central, //备份在统一的备份目录中，文件名由完整路径转换而来
+}
+
+///定时执行的功能：是否开启，以及执行的间隔
+#[derive(Clone, Copy)]
+pub struct Timer {
+    pub enabled: bool,
+    pub interval: Duration,
+}
+
+///可以按文件类型分别设置的开关：`key = on` 设置所有文件类型，`key.<filetype> = off` 只设置一种，后者优先
+#[derive(Clone, Default)]
+pub struct FileTypeSwitch {
+    default: bool,
+    by_type: HashMap<FileType, bool>,
+}
+
+impl FileTypeSwitch {
+    fn new(default: bool) -> Self {
+        Self { default, by_type: HashMap::new() }
+    }
+
+    ///某种文件类型是否打开
+    pub fn get(&self, file_type: FileType) -> bool {
+        self.by_type.get(&file_type).copied().unwrap_or(self.default)
+    }
+
+    ///没有指定文件类型时修改所有文件类型的默认值
+    fn set(&mut self, file_type: Option<FileType>, value: bool) {
+        match file_type {
+            Some(file_type) => {
+                self.by_type.insert(file_type, value);
+            },
+            None => self.default = value,
+        }
+    }
+}
+
+///编辑器的配置，启动时从配置文件中读取
+/// 配置文件位于 `$XDG_CONFIG_HOME/hecto/config` 或 `~/.config/hecto/config`，每行一个 `key = value`，`#` 开头的行为注释
+pub struct Settings {
+    pub legacy_encoding: &'static Encoding, //文件不是 UTF-8 且没有 BOM 时使用的编码
+    pub backup: BackupMode,
+    pub backup_dir: PathBuf, //backup = central 时使用的目录
+    pub backup_keep: usize,  //每个文件保留的备份数量
+    pub swap: Timer,         //是否以及每隔多久将未保存的修改写入交换文件
+    pub autosave: Timer,     //是否自动保存已有文件名的文件，以及没有输入多久之后保存
+    pub mouse: bool,             //是否捕获鼠标，用于点击标签切换buffer
+    pub expandtab: bool,     //无法从内容判断缩进方式时，Tab键是否插入空格
+    pub indent_width: usize, //expandtab 时每一级缩进的空格数
+    pub autopairs: FileTypeSwitch, //键入括号和引号时是否自动插入闭合符号
+    pub trim_trailing_whitespace: FileTypeSwitch, //保存时删除行末的空白
+    pub final_newline: FileTypeSwitch,            //保存时保证文件以一个换行符结尾
+    pub trim_trailing_blank_lines: FileTypeSwitch, //保存时删除文件末尾的空行
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            legacy_encoding: WINDOWS_1252,
+            backup: BackupMode::default(),
+            backup_dir: Self::state_dir().join("backup"),
+            backup_keep: 1,
+            swap: Timer { enabled: true, interval: Duration::from_secs(4) },
+            autosave: Timer { enabled: false, interval: Duration::from_secs(5) },
+            mouse: false,
+            expandtab: false,
+            indent_width: 4,
+            autopairs: FileTypeSwitch::new(true),
+            //Markdown 行末的两个空格表示换行，默认不删除
+            trim_trailing_whitespace: FileTypeSwitch {
+                default: true,
+                by_type: HashMap::from([(FileType::Markdown, false)]),
+            },
+            final_newline: FileTypeSwitch::new(true),
+            trim_trailing_blank_lines: FileTypeSwitch::new(false),
+        }
+    }
+}
+
+impl Settings {
+    ///读取配置文件，返回配置以及其中无法识别的条目；配置文件不存在时使用默认配置
+    pub fn load() -> (Self, Vec<String>) {
+        let mut settings = Self::default();
+        let mut errors = Vec::new();
+
+        let Some(contents) = Self::config_path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return (settings, errors);
+        };
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => settings.set(key.trim(), value.trim()),
+                None => Err(format!("invalid config line: {line}")),
+            };
+            if let Err(err) = result {
+                errors.push(err);
+            }
+        }
+        (settings, errors)
+    }
+
+    ///修改一项配置
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        //按文件类型设置的开关，如 `autopairs.markdown`
+        let (name, file_type) = match key.split_once('.') {
+            Some((name, type_name)) => {
+                let file_type = FileType::from_name(type_name).ok_or_else(|| format!("unknown file type: {type_name}"))?;
+                (name, Some(file_type))
+            },
+            None => (key, None),
+        };
+        if let Some(switch) = self.file_type_switch(name) {
+            switch.set(file_type, Self::parse_bool(key, value)?);
+            return Ok(());
+        }
+        match key {
+            "legacy_encoding" => {
+                self.legacy_encoding = Encoding::for_label(value.as_bytes())
+                    .ok_or_else(|| format!("unknown encoding: {value}"))?;
+            }
+            "backup" => {
+                self.backup = match value {
+                    "off" => BackupMode::Off,
+                    "local" => BackupMode::Local,
+                    "central" => BackupMode::Central,
+                    _ => return Err(format!("backup must be off, local or central: {value}")),
+                };
+            }
+            "backup_dir" => self.backup_dir = PathBuf::from(pathcompleter::expand(value)),
+            "backup_keep" => self.backup_keep = Self::parse_number(key, value)?.max(1),
+            "swap" => self.swap.enabled = Self::parse_bool(key, value)?,
+            "swap_interval" => self.swap.interval = Self::parse_seconds(key, value)?,
+            "autosave" => self.autosave.enabled = Self::parse_bool(key, value)?,
+            "autosave_delay" => self.autosave.interval = Self::parse_seconds(key, value)?,
+            "mouse" => self.mouse = Self::parse_bool(key, value)?,
+            "expandtab" => self.expandtab = Self::parse_bool(key, value)?,
+            "indent_width" => self.indent_width = Self::parse_number(key, value)?.max(1),
+            _ => return Err(format!("unknown setting: {key}")),
+        }
+        Ok(())
+    }
+
+    ///名称对应的按文件类型设置的开关
+    fn file_type_switch(&mut self, name: &str) -> Option<&mut FileTypeSwitch> {
+        match name {
+            "autopairs" => Some(&mut self.autopairs),
+            "trim_trailing_whitespace" => Some(&mut self.trim_trailing_whitespace),
+            "final_newline" => Some(&mut self.final_newline),
+            "trim_trailing_blank_lines" => Some(&mut self.trim_trailing_blank_lines),
+            _ => None,
+        }
+    }
+
+    ///解析开关类的设置，如 on/off
+    pub fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+        match value {
+            "on" | "true" | "yes" => Ok(true),
+            "off" | "false" | "no" => Ok(false),
+            _ => Err(format!("{key} must be on or off: {value}")),
+        }
+    }
+
+    fn parse_number(key: &str, value: &str) -> Result<usize, String> {
+        value
+            .parse()
+            .map_err(|_| format!("{key} must be a number: {value}"))
+    }
+
+    ///以秒为单位的时间间隔
+    fn parse_seconds(key: &str, value: &str) -> Result<Duration, String> {
+        let seconds = u64::try_from(Self::parse_number(key, value)?).map_err(|_| format!("{key} is too large: {value}"))?;
+        Ok(Duration::from_secs(seconds))
+    }
+
+    ///保存备份等运行时文件的目录：`$XDG_STATE_HOME/hecto` 或 `~/.local/state/hecto`
+    fn state_dir() -> PathBuf {
+        env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+            .unwrap_or_else(env::temp_dir)
+            .join("hecto")
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("hecto").join("config"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_intervals_in_seconds() {
+        let mut settings = Settings::default();
+        settings.set("swap_interval", "10").unwrap();
+        assert_eq!(settings.swap.interval, Duration::from_secs(10));
+        assert!(settings.set("swap_interval", "-1").is_err());
+        assert!(settings.set("swap_interval", "soon").is_err());
+    }
+}
@@ -40,10 +40,22 @@ impl UIComponent for StatusBar {
    fn draw(&mut self, position_row:usize) -> Result<(),Error> {
         let line_count = self.current_status.line_count_to_string();
         let modified_indicator = self.current_status.modified_indicator_to_string();
+        let new_file_indicator = self.current_status.new_file_indicator_to_string();
+        let read_only_indicator = self.current_status.read_only_indicator_to_string();
+        let file_name = [self.current_status.file_name.as_str(), &new_file_indicator, &read_only_indicator]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
 
-        let beginning = format!("{} - {line_count} {modified_indicator}", &self.current_status.file_name);   
+        let beginning = format!("{file_name} - {line_count} {modified_indicator}");   
 
-        let position_indicator = self.current_status.position_indicator_to_string();
+        let position_indicator = format!(
+            "{} | {} | {}",
+            self.current_status.encoding,
+            self.current_status.line_ending_indicator_to_string(),
+            self.current_status.position_indicator_to_string()
+        );
         let right_left = self.size.columns.saturating_sub(beginning.len());
         let status = format!("{beginning}{position_indicator:>right_left$}");
 
@@ -0,0 +1,126 @@
+use std::io::Error;
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+use crate::editor::position::Position;
+use crate::editor::size::Size;
+use super::{
+    terminal::Terminal,
+    uicomponent::UIComponent,
+};
+
+///标签栏中的一个文档
+#[derive(Clone, Eq, PartialEq)]
+pub struct Tab {
+    pub name: String,
+    pub is_modified: bool,
+}
+
+impl Tab {
+    fn label(&self) -> String {
+        let marker = if self.is_modified { "*" } else { "" };
+        format!(" {}{marker} ", self.name)
+    }
+}
+
+///文本区域上方的一行，列出打开的文档，当前文档反色显示
+/// 标签太多放不下时，滚动使当前标签可见，两端用 < > 提示还有被隐藏的标签
+#[derive(Default)]
+pub struct TabBar {
+    tabs: Vec<Tab>,
+    active: usize,
+    first_visible: usize, //最左边显示的标签
+    columns: Vec<(usize, Range<usize>)>, //上一次渲染时每个可见标签所在的列，用于响应鼠标点击
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl TabBar {
+    ///更新标签，内容有变化时才重新渲染
+    pub fn update_tabs(&mut self, tabs: Vec<Tab>, active: usize) {
+        if tabs != self.tabs || active != self.active {
+            self.tabs = tabs;
+            self.active = active;
+            self.set_needs_redraw(true);
+        }
+    }
+
+    ///点击的列上的标签
+    pub fn tab_at(&self, column: usize) -> Option<usize> {
+        self.columns
+            .iter()
+            .find(|(_, range)| range.contains(&column))
+            .map(|(index, _)| *index)
+    }
+
+    ///从 first 开始可以完整显示的标签数量，width 为可用的列数
+    fn visible_count(&self, first: usize, width: usize) -> usize {
+        let mut used: usize = 0;
+        let mut count: usize = 0;
+        for tab in self.tabs.iter().skip(first) {
+            used = used.saturating_add(tab.label().width()).saturating_add(1);
+            if used > width {
+                break;
+            }
+            count = count.saturating_add(1);
+        }
+        count
+    }
+
+    ///滚动使当前标签可见，两端各预留一列给 < >
+    fn scroll_active_into_view(&mut self) {
+        let width = self.size.columns.saturating_sub(2);
+        if self.active < self.first_visible {
+            self.first_visible = self.active;
+        }
+        while self.first_visible < self.active
+            && self.first_visible.saturating_add(self.visible_count(self.first_visible, width)) <= self.active
+        {
+            self.first_visible = self.first_visible.saturating_add(1);
+        }
+        self.first_visible = self.first_visible.min(self.tabs.len().saturating_sub(1));
+    }
+}
+
+impl UIComponent for TabBar {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+
+    fn draw(&mut self, position_row: usize) -> Result<(), Error> {
+        self.scroll_active_into_view();
+        let width = self.size.columns.saturating_sub(2);
+        let count = self.visible_count(self.first_visible, width).max(1);
+
+        Terminal::print_row(position_row, "")?;
+        self.columns.clear();
+        let left = if self.first_visible > 0 { "<" } else { " " };
+        Terminal::print_at(Position { column: 0, row: position_row }, left)?;
+
+        let mut column = 1;
+        for (index, tab) in self.tabs.iter().enumerate().skip(self.first_visible).take(count) {
+            let label: String = tab.label().chars().take(width).collect();
+            let label_width = label.width();
+            let position = Position { column, row: position_row };
+            if index == self.active {
+                Terminal::print_inverted_at(position, &label)?;
+            } else {
+                Terminal::print_at(position, &label)?;
+            }
+            self.columns.push((index, column..column.saturating_add(label_width)));
+            column = column.saturating_add(label_width).saturating_add(1);
+        }
+
+        if self.first_visible + count < self.tabs.len() {
+            Terminal::print_at(Position { column: self.size.columns.saturating_sub(1), row: position_row }, ">")?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use crossterm::cursor::{MoveTo,Hide,Show};
 use crossterm::style::{Attribute, Print};
 use crossterm::{queue, Command};
+use crossterm::event::{EnableFocusChange, DisableFocusChange, EnableMouseCapture, DisableMouseCapture};
 use crossterm::terminal::{Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode, size};
 use std::io::{stdout,Write,Error};
 use crate::editor::position::Position;
@@ -11,6 +12,8 @@ pub struct Terminal {}
 impl Terminal {
     pub fn terminate() -> Result<(),std::io::Error> {
         Self::leave_alternate_screen()?; //结束的时候，离开副屏幕
+        Self::queue_command(DisableFocusChange)?;
+        Self::queue_command(DisableMouseCapture)?;
         Self::enable_line_wrap()?; //开启自动换行
         Self::show_caret()?;
         Self::execute()?;
@@ -22,11 +25,22 @@ impl Terminal {
         enable_raw_mode()?;//开启终端的原始模式
         Self::enter_alternate_screen()?;//进入副屏幕
         Self::diable_line_wrap()?;//关闭自动换行
+        Self::queue_command(EnableFocusChange)?;//接收终端失去、获得焦点的事件
         Self::clear_screen()?;
         Self::execute()?;
         Ok(())
     }
 
+    ///开启或关闭鼠标捕获，开启后可以点击标签切换buffer，但终端本身的选择文字需要按住 Shift
+    pub fn set_mouse_capture(enabled: bool) -> Result<(),Error> {
+        if enabled {
+            Self::queue_command(EnableMouseCapture)?;
+        } else {
+            Self::queue_command(DisableMouseCapture)?;
+        }
+        Self::execute()
+    }
+
     ///离开副屏幕指令
     pub fn leave_alternate_screen() -> Result<(),Error> {
         Self::queue_command(LeaveAlternateScreen)?;
@@ -101,6 +115,17 @@ impl Terminal {
         queue!(stdout(),command)
     }
 
+    ///在指定位置打印，不清除该行的其他内容，用于分屏时只更新屏幕的一部分
+    pub fn print_at(position: Position, text: &str) -> Result<(),Error> {
+        Self::move_caret_to(position)?;
+        Self::print(text)
+    }
+
+    ///在指定位置反色打印
+    pub fn print_inverted_at(position: Position, text: &str) -> Result<(),Error> {
+        Self::print_at(position, &format!("{}{text}{}", Attribute::Reverse, Attribute::Reset))
+    }
+
     ///用于将该行高亮显示
     pub fn print_inverted_color_row(row:usize, line_text:&str) -> Result<(),Error> {
         Self::print_row(row,&format!("{}{}{}", Attribute::Reverse,line_text,Attribute::Reset))
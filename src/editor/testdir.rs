@@ -0,0 +1,41 @@
+use std::{
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+};
+
+///测试专用的空目录，位于系统临时目录下；离开作用域时删除，测试 panic 时也不会留下
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    ///name 区分不同的测试，加上进程号避免与同时运行的其他测试冲突
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("hecto-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -1,24 +1,47 @@
 mod buffer;
 mod fileinfo;
+mod atomicwrite;
+mod backup;
+mod swap;
 use std::io::Error;
 use std::cmp::min;
+use std::cell::RefCell;
+use unicode_width::UnicodeWidthStr;
+use std::rc::Rc;
+use std::path::{Path,PathBuf};
+use std::ops::{Range,RangeInclusive};
 use super::{NAME,VERSION};
 use super::terminal::Terminal;
 use crate::editor::size::Size;
-use super::documentstatus::DocumentStatus;
+use super::documentstatus::{DocumentStatus,LineEndingStatus};
 use super::uicomponent::UIComponent;
 use super::command::{Edit,Move};
 use crate::editor::Position;
-use crate::editor::view::buffer::Buffer;
+pub use crate::editor::view::buffer::{Buffer,BufferOptions};
+use crate::editor::view::buffer::BufferKind;
 use crate::editor::line::Line;
+use crate::editor::lineending::LineEnding;
+use crate::editor::encoding::FileEncoding;
+use crate::editor::settings::Settings;
+use crate::editor::indentation::Indentation;
+use crate::editor::diff;
 use fileinfo::FileInfo;
+pub use fileinfo::DiskChange;
 
-#[derive(Copy, Clone,Default)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Location {
     pub grapheme_index:usize,
     pub line_index:usize,
 }
 
+///自动配对时键入一个字符的处理方式
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+enum PairAction {
+    Insert, //只插入键入的字符
+    Skip, //光标后已经是键入的闭合符号，跳过它
+    InsertPair(char), //插入键入的字符，并在光标后插入闭合符号
+}
+
 ///理解可视窗口的大小比较重要：正如终端的尺寸在缩小或扩展之后是一定的。
 /// scroll_offset 表示从文本的第几行开始渲染，是一个偏移量。scroll_offset = 1, 则会从 文本的第二行开始渲染(起始行号为0),直到铺满屏幕
 /// 很多时候，文本的大小不能在一定尺寸的可视终端窗口大小中完全看完，当光标不断变化，直到光标超出当前可视窗口的渲染的范围的时候；则需要改变渲染的偏移量，让光标想要查看的位置重新渲染。
@@ -26,11 +49,14 @@ pub struct Location {
 /// 光标移动到(2,0),想要查看第2行，超出了渲染的范围(0~1); 更改渲染的偏移量scroll_offset = 1,可视文本范围(1~2)
 #[derive(Default)]
 pub struct View {
-    buffer: Buffer,
+    buffer: Rc<RefCell<Buffer>>, //同一个buffer可以同时显示在多个view中
     need_redraw: bool,
     size: Size,
     text_location: Location,//文本中的第几行的第几个 grapheme
     scroll_offset: Position,//物理屏幕上的行列
+    origin: Position, //view在屏幕上的左上角，分屏时不在(0,0)
+    drawn_revision: u64, //上一次渲染时buffer的revision，其他view修改了同一个buffer时需要重新渲染
+    selection_anchor: Option<Location>, //选择的起点，另一端是光标；None 表示没有选择
 }
 
 
@@ -38,18 +64,81 @@ impl View {
     ///返回文本信息
     pub fn get_status(&self) -> DocumentStatus {
         DocumentStatus { 
-            total_lines: self.buffer.height(),
+            total_lines: self.buffer.borrow().height(),
             current_line_index: self.text_location.line_index,
-            is_modified: self.buffer.dirty,
-            file_name: format!("{}",self.buffer.file_info), 
+            is_modified: self.buffer.borrow().dirty,
+            is_new_file: self.buffer.borrow().is_new_file,
+            is_read_only: self.buffer.borrow().options.read_only,
+            line_ending: LineEndingStatus {
+                style: self.buffer.borrow().line_ending_style(),
+                no_final_newline: self.buffer.borrow().no_final_newline,
+            },
+            encoding: self.buffer.borrow().file_info.get_encoding(),
+            file_name: format!("{}",self.buffer.borrow().file_info), 
         }
     }
 
 
 
-    ///渲染一行
-    pub fn render_line(row: usize, line_text: &str) -> Result<(),Error> {
-        Terminal::print_row(row, line_text)
+    ///渲染一行，只覆盖view所在的列，不足的部分用空格补齐
+    fn render_line(&self, row: usize, line_text: &str) -> Result<(),Error> {
+        let padding = self.size.columns.saturating_sub(line_text.width());
+        Terminal::print_at(
+            Position { column: self.origin.column, row },
+            &format!("{line_text}{}", " ".repeat(padding)),
+        )
+    }
+
+    ///一行中被选择的部分所在的列；选择延续到下一行时，行末多占一列表示换行符也被选择
+    fn selected_columns(&self, line_index: usize, line: &Line) -> Option<Range<usize>> {
+        let (start, end) = self.selection()?;
+        if line_index < start.line_index || line_index > end.line_index {
+            return None;
+        }
+        let from = if line_index == start.line_index { start.grapheme_index } else { 0 };
+        let columns = if line_index == end.line_index {
+            line.width_until(from)..line.width_until(end.grapheme_index)
+        } else {
+            line.width_until(from)..line.width().saturating_add(1)
+        };
+        Some(columns)
+    }
+
+    ///光标在括号上时，与之配对的括号的位置
+    fn matching_bracket(&self) -> Option<Location> {
+        self.buffer.borrow().matching_bracket(self.text_location)
+    }
+
+    ///渲染带有选择的一行，被选择的部分反色显示
+    fn render_selected_line(&self, row: usize, line: &Line, visible: Range<usize>, selected: &Range<usize>) -> Result<(),Error> {
+        let from = selected.start.clamp(visible.start, visible.end);
+        let to = selected.end.clamp(visible.start, visible.end);
+        let before = line.get_visible_graheme(visible.start..from);
+        let mut inside = line.get_visible_graheme(from..to);
+        inside.push_str(&" ".repeat(to.saturating_sub(from).saturating_sub(inside.width())));
+        let after = line.get_visible_graheme(to..visible.end);
+        let padding = visible.end.saturating_sub(to).saturating_sub(after.width());
+
+        Terminal::print_at(Position { column: self.origin.column, row }, &before)?;
+        Terminal::print_inverted_at(
+            Position { column: self.origin.column.saturating_add(from.saturating_sub(visible.start)), row },
+            &inside,
+        )?;
+        Terminal::print_at(
+            Position { column: self.origin.column.saturating_add(to.saturating_sub(visible.start)), row },
+            &format!("{after}{}", " ".repeat(padding)),
+        )
+    }
+
+    ///设置view在屏幕上的位置和大小
+    pub fn set_area(&mut self, origin: Position, size: Size) {
+        self.origin = origin;
+        self.resize(size);
+    }
+
+    ///view在屏幕上的左上角
+    pub const fn origin(&self) -> Position {
+        self.origin
     }
 
 
@@ -81,6 +170,47 @@ impl View {
     // Right,
     // Down,
     pub fn handle_move_command(&mut self, command: Move) {
+        self.clear_selection();
+        self.move_caret(command);
+    }
+
+    ///按住Shift移动光标：第一次移动时在原来的位置放下选择的起点
+    pub fn handle_select_command(&mut self, command: Move) {
+        self.selection_anchor.get_or_insert(self.text_location);
+        self.move_caret(command);
+        self.set_needs_redraw(true);
+    }
+
+    ///取消选择
+    fn clear_selection(&mut self) {
+        if self.selection_anchor.take().is_some() {
+            self.set_needs_redraw(true);
+        }
+    }
+
+    ///选择的范围，起点在前；没有选择或者范围为空时返回 None
+    fn selection(&self) -> Option<(Location, Location)> {
+        let anchor = self.selection_anchor?;
+        if anchor == self.text_location {
+            return None;
+        }
+        Some((anchor.min(self.text_location), anchor.max(self.text_location)))
+    }
+
+    ///选择所涉及的行；选择结束在某一行的开头时，不包括该行
+    fn selected_lines(&self) -> Option<RangeInclusive<usize>> {
+        let (start, end) = self.selection()?;
+        let last = if end.grapheme_index == 0 && end.line_index > start.line_index {
+            end.line_index.saturating_sub(1)
+        } else {
+            end.line_index
+        };
+        Some(start.line_index..=last)
+    }
+
+    fn move_caret(&mut self, command: Move) {
+        //配对括号的高亮随光标变化，需要重新绘制
+        let had_partner = self.matching_bracket().is_some();
         match command {
             Move::Up => {
                 self.move_up(1);
@@ -108,6 +238,14 @@ impl View {
             Move::EndofLine => {
                 self.move_to_end_of_line();
             }
+            Move::MatchingBracket => {
+                if let Some(partner) = self.matching_bracket() {
+                    self.text_location = partner;
+                }
+            }
+        }
+        if had_partner || self.matching_bracket().is_some() {
+            self.set_needs_redraw(true);
         }
         self.scroll_text_location_into_view();//将当前文本位置移动到可见的范围
     }
@@ -117,28 +255,398 @@ impl View {
 //    InsertNewline,
 //    Delete,
 //    DeleteBackward, //Backsapce
-    pub fn handle_edit_command(&mut self, command: Edit) {
+    ///只读模式下拒绝修改，返回提示信息
+    pub fn handle_edit_command(&mut self, command: Edit) -> Result<(),String> {
+        self.check_writable()?;
+        //除了缩进和注释，其余编辑都会取消选择
+        if !matches!(command, Edit::Insert('\t') | Edit::Dedent | Edit::ToggleComment) {
+            self.clear_selection();
+        }
         match command {
-           Edit::Insert(ch) => self.insert_char(ch), 
+           Edit::Insert('\t') => self.insert_tab(),
+           Edit::Dedent => self.dedent(),
+           Edit::ToggleComment => self.toggle_comment()?,
+           Edit::Insert(ch) => self.type_char(ch), 
            Edit::InsertNewline => self.insert_newline(),
            Edit::Delete => self.delete(),
            Edit::DeleteBackward => self.delete_backward(),
         }     
+        Ok(())
     }
 
-    /// 保存当前信息到当前的文件地址
-    pub fn save(&mut self) -> Result<(),Error> {
-        self.buffer.save()
+    ///只读模式下返回提示信息
+    pub fn check_writable(&self) -> Result<(),String> {
+        let kind = self.buffer.borrow().kind;
+        if kind == BufferKind::Directory {
+            Err(String::from("Directory listing is read-only"))
+        } else if kind == BufferKind::SearchResults {
+            Err(String::from("Search results are read-only, press Enter to open a match"))
+        } else if self.buffer.borrow().options.read_only {
+            Err(String::from("File is read-only, use `set noreadonly` to edit"))
+        } else {
+            Ok(())
+        }
+    }
+
+    ///Tab键：有选择时给选择涉及的每一行增加一级缩进；光标在行首的缩进中时给当前行增加一级缩进
+    /// 其余情况下，使用空格缩进时插入空格直到下一个缩进的位置，否则插入Tab
+    fn insert_tab(&mut self) {
+        if let Some(lines) = self.selected_lines() {
+            self.shift_lines(lines, true);
+            return;
+        }
+        let Location { line_index, grapheme_index } = self.text_location;
+        let text = {
+            let buffer = self.buffer.borrow();
+            let indentation = buffer.options.indentation;
+            match buffer.lines.get(line_index) {
+                Some(line) => Self::tab_text(line, grapheme_index, indentation),
+                None => Self::tab_text(&Line::default(), 0, indentation),
+            }
+        };
+        match text {
+            Some(text) => text.chars().for_each(|character| self.insert_char(character)),
+            None => self.shift_lines(line_index..=line_index, true),
+        }
+    }
+
+    ///Tab键在光标处插入的文本；光标在非空行行首的缩进中时返回 None，表示给该行增加一级缩进
+    fn tab_text(line: &Line, grapheme_index: usize, indentation: Indentation) -> Option<String> {
+        let blank_until = |end: usize| {
+            (0..end).filter_map(|index| line.grapheme_at(index)).all(|grapheme| grapheme == " " || grapheme == "\t")
+        };
+        if blank_until(grapheme_index) && !blank_until(line.grapheme_count()) {
+            return None;
+        }
+        match indentation {
+            Indentation::Tabs => Some(String::from("\t")),
+            Indentation::Spaces(width) => {
+                let column = line.width_until(grapheme_index);
+                Some(" ".repeat(width.saturating_sub(column % width)))
+            },
+        }
+    }
+
+    ///Shift-Tab：选择涉及的每一行，没有选择时当前行，减少一级缩进
+    fn dedent(&mut self) {
+        let lines = self.selected_lines().unwrap_or(self.text_location.line_index..=self.text_location.line_index);
+        self.shift_lines(lines, false);
+    }
+
+    ///增加或减少若干行的缩进，选择的起点和光标随所在行的文本移动
+    fn shift_lines(&mut self, lines: RangeInclusive<usize>, indent: bool) {
+        let first = *lines.start();
+        let deltas = if indent {
+            self.buffer.borrow_mut().indent_lines(lines)
+        } else {
+            self.buffer.borrow_mut().dedent_lines(lines)
+        };
+        let shift = |location: &mut Location| {
+            let Some(delta) = location.line_index.checked_sub(first).and_then(|offset| deltas.get(offset)) else {
+                return;
+            };
+            location.grapheme_index = if indent {
+                location.grapheme_index.saturating_add(*delta)
+            } else {
+                location.grapheme_index.saturating_sub(*delta)
+            };
+        };
+        shift(&mut self.text_location);
+        if let Some(anchor) = &mut self.selection_anchor {
+            shift(anchor);
+        }
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    ///Ctrl-/：切换选择涉及的每一行，没有选择时当前行的注释，注释的符号由文件类型决定
+    fn toggle_comment(&mut self) -> Result<(),String> {
+        let file_type = self.buffer.borrow().file_type();
+        let Some(markers) = file_type.comment_markers() else {
+            return Err(String::from("No comment syntax for this file type"));
+        };
+        let lines = self.selected_lines().unwrap_or(self.text_location.line_index..=self.text_location.line_index);
+        let first = *lines.start();
+        let (comment, changes) = self.buffer.borrow_mut().toggle_comment(lines, markers);
+        //注释开头之后的位置随文本移动，落在被删除的注释开头中的位置移到注释原来的位置
+        let buffer = self.buffer.borrow();
+        let shift = |location: &mut Location| {
+            let Some(Some((column, length))) = location.line_index.checked_sub(first).and_then(|offset| changes.get(offset)) else {
+                return;
+            };
+            if location.grapheme_index > *column {
+                location.grapheme_index = if comment {
+                    location.grapheme_index.saturating_add(*length)
+                } else {
+                    location.grapheme_index.saturating_sub(*length).max(*column)
+                };
+            }
+            let count = buffer.lines.get(location.line_index).map_or(0, Line::grapheme_count);
+            location.grapheme_index = location.grapheme_index.min(count);
+        };
+        shift(&mut self.text_location);
+        if let Some(anchor) = &mut self.selection_anchor {
+            shift(anchor);
+        }
+        drop(buffer);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        Ok(())
+    }
+
+    ///当前buffer的缩进方式
+    pub fn indentation(&self) -> Indentation {
+        self.buffer.borrow().options.indentation
+    }
+
+    ///修改当前buffer的缩进方式，不改变已有的内容
+    pub fn set_indentation(&mut self, indentation: Indentation) {
+        self.buffer.borrow_mut().options.indentation = indentation;
+    }
+
+    ///整个文件在Tab与空格之间转换，返回内容是否发生了变化
+    pub fn retab(&mut self, expand: bool, width: usize) -> bool {
+        let changed = self.buffer.borrow_mut().retab(expand, width);
+        self.after_contents_replaced();
+        changed
+    }
+
+    ///切换只读模式
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.buffer.borrow_mut().options.read_only = read_only;
+    }
+
+    /// 保存当前信息到当前的文件地址，保存前的整理可能删除光标所在的内容，光标移到有效的位置
+    pub fn save(&mut self, settings: &Settings) -> Result<(),Error> {
+        let result = self.buffer.borrow_mut().save(settings);
+        self.after_contents_replaced();
+        result
     }
 
     /// 保存当前信息到指定的文件地址
-    pub fn save_as(&mut self, file_name: &str) -> Result<(),Error> {
-        self.buffer.save_as(file_name)
+    pub fn save_as(&mut self, file_path: &Path, settings: &Settings) -> Result<(),Error> {
+        let result = self.buffer.borrow_mut().save_as(file_path, settings);
+        self.after_contents_replaced();
+        result
+    }
+
+    ///撤销最近一次保存时对内容的整理，没有可以撤销的内容时返回 false
+    pub fn undo_save_hooks(&mut self) -> bool {
+        let restored = self.buffer.borrow_mut().undo_save_hooks();
+        if restored {
+            self.after_contents_replaced();
+        }
+        restored
+    }
+
+    ///当前文件最新的备份
+    pub fn newest_backup(&self, settings: &Settings) -> Option<PathBuf> {
+        self.buffer.borrow().file_info.get_path().and_then(|path| backup::newest(path, settings))
+    }
+
+    ///比较另一个文件(如备份)与当前内容，没有差异时返回空
+    pub fn diff_against(&self, path: &Path) -> Result<Vec<String>,Error> {
+        let other = Buffer::read_lines(path, self.buffer.borrow().file_info.get_encoding())?;
+        Ok(diff::unified_diff(
+            &path.display().to_string(),
+            &format!("{} (buffer)", self.buffer.borrow().file_info),
+            &other,
+            &self.buffer.borrow().text_lines(),
+        ))
+    }
+
+    ///比较当前内容与交换文件中未保存的内容
+    pub fn diff_swap(&self, swap: &Path) -> Result<Vec<String>,Error> {
+        let recovered = Buffer::read_lines(swap, FileEncoding::default())?;
+        Ok(diff::unified_diff(
+            &format!("{}", self.buffer.borrow().file_info),
+            &swap.display().to_string(),
+            &self.buffer.borrow().text_lines(),
+            &recovered,
+        ))
+    }
+
+    ///用另一个文件(如备份)的内容替换当前内容
+    pub fn restore_from(&mut self, path: &Path) -> Result<(),Error> {
+        let encoding = self.buffer.borrow().file_info.get_encoding();
+        self.buffer.borrow_mut().restore_from(path, encoding)?;
+        self.after_contents_replaced();
+        Ok(())
+    }
+
+    ///用交换文件中的内容恢复上一次未保存的修改
+    pub fn recover_from_swap(&mut self, swap: &Path) -> Result<(),Error> {
+        self.buffer.borrow_mut().restore_from(swap, FileEncoding::default())?;
+        self.after_contents_replaced();
+        Ok(())
+    }
+
+    ///上一次编辑异常结束时留下的交换文件
+    pub fn pending_swap(&self) -> Option<PathBuf> {
+        self.buffer.borrow().pending_swap()
+    }
+
+    ///不再写入交换文件，保留其他进程留下的交换文件
+    pub fn suspend_swap(&mut self) {
+        self.buffer.borrow_mut().swap.suspended = true;
+    }
+
+    ///删除自己写过的交换文件
+    pub fn remove_swap(&mut self) -> Result<(),Error> {
+        self.buffer.borrow_mut().remove_swap()
+    }
+
+    ///内容被整体替换之后，保证光标位置合法并重新渲染
+    fn after_contents_replaced(&mut self) {
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    ///显示只读临时内容(如比较结果)的view
+    pub fn scratch(title: &str, lines: &[String]) -> Self {
+        Self {
+            buffer: Rc::new(RefCell::new(Buffer::scratch(title, lines))),
+            need_redraw: true,
+            ..Self::default()
+        }
+    }
+
+    ///分屏时新窗口的view：显示同一个buffer，光标和滚动位置与当前相同
+    pub fn split(&self) -> Self {
+        Self {
+            buffer: Rc::clone(&self.buffer),
+            need_redraw: true,
+            text_location: self.text_location,
+            scroll_offset: self.scroll_offset,
+            ..Self::default()
+        }
+    }
+
+    ///当前显示的buffer
+    pub const fn buffer(&self) -> &Rc<RefCell<Buffer>> {
+        &self.buffer
+    }
+
+    ///切换显示的buffer，记住离开时的光标位置，回到之前的buffer时恢复
+    pub fn set_buffer(&mut self, buffer: Rc<RefCell<Buffer>>) {
+        self.selection_anchor = None;
+        self.buffer.borrow_mut().last_location = self.text_location;
+        self.text_location = buffer.borrow().last_location;
+        self.buffer = buffer;
+        self.scroll_offset = Position::default();
+        self.after_contents_replaced();
+    }
+
+    ///统一转换文件的换行符
+    pub fn convert_line_endings(&mut self, ending: LineEnding) {
+        self.buffer.borrow_mut().convert_line_endings(ending);
+    }
+
+    ///以指定的编码重新打开当前文件
+    pub fn reload_with_encoding(&mut self, encoding: FileEncoding) -> Result<(),Error> {
+        self.buffer.borrow_mut().reload_with_encoding(encoding)?;
+        self.after_contents_replaced();
+        Ok(())
+    }
+
+    ///当前文件读取时是否有无法解码的字节
+    pub fn has_decode_errors(&self) -> bool {
+        self.buffer.borrow().file_info.is_lossy()
+    }
+
+    ///指定保存时使用的编码
+    pub fn set_encoding(&mut self, encoding: FileEncoding) {
+        self.buffer.borrow_mut().set_encoding(encoding);
+    }
+
+    ///检查当前文件是否被其他程序修改或删除
+    pub fn check_disk(&mut self) -> DiskChange {
+        self.buffer.borrow_mut().check_disk()
+    }
+
+    ///保留当前内容，忽略磁盘上的这次修改
+    pub fn acknowledge_disk_change(&mut self) {
+        self.buffer.borrow_mut().acknowledge_disk_change();
+    }
+
+    ///放弃当前内容，重新读取磁盘上的文件，光标尽量留在原来的行
+    pub fn reload(&mut self) -> Result<(),Error> {
+        let encoding = self.buffer.borrow().file_info.get_encoding();
+        self.reload_with_encoding(encoding)
+    }
+
+    ///当前文件的路径
+    pub fn file_path(&self) -> Option<PathBuf> {
+        self.buffer.borrow().file_info.get_path().map(Path::to_path_buf)
+    }
+
+    ///判断给定路径是否就是当前打开的文件
+    pub fn is_current_file(&self, path: &Path) -> bool {
+        self.buffer.borrow().file_info.is_same_file(path)
+    }
+
+    ///当前文件所在的目录，用于解析提示栏中输入的相对路径
+    /// 目录列表的基准就是该目录本身
+    pub fn file_directory(&self) -> Option<PathBuf> {
+        if self.is_directory() {
+            return self.file_path();
+        }
+        self.buffer.borrow().file_info.get_directory().map(Path::to_path_buf)
+    }
+
+    ///当前显示的是否为目录列表
+    pub fn is_directory(&self) -> bool {
+        self.buffer.borrow().kind == BufferKind::Directory
+    }
+
+    ///目录列表中光标所在的条目的路径
+    pub fn selected_entry(&self) -> Option<PathBuf> {
+        self.buffer.borrow().directory_entry(self.text_location.line_index)
+    }
+
+    ///当前显示的是否为搜索结果
+    pub fn is_search_results(&self) -> bool {
+        self.buffer.borrow().kind == BufferKind::SearchResults
+    }
+
+    ///搜索结果中光标所在的一行对应的文件和位置
+    pub fn selected_result(&self) -> Option<(PathBuf, Location)> {
+        self.buffer.borrow().result_target(self.text_location.line_index)
+    }
+
+    ///将光标移到指定的位置，并滚动使其可见
+    pub fn jump_to(&mut self, location: Location) {
+        self.text_location = location;
+        self.after_contents_replaced();
+    }
+
+    ///在当前的目录列表中显示另一个目录，光标回到第一行
+    pub fn open_directory(&mut self, path: &Path) -> Result<(),Error> {
+        self.buffer.borrow_mut().load_directory(path)?;
+        self.text_location = Location::default();
+        self.scroll_offset = Position::default();
+        self.after_contents_replaced();
+        Ok(())
+    }
+
+    ///重新读取目录列表(如创建、删除文件之后)，光标尽量留在原来的行
+    pub fn refresh_directory(&mut self) -> Result<(),Error> {
+        let Some(path) = self.file_path() else {
+            return Ok(());
+        };
+        self.buffer.borrow_mut().load_directory(&path)?;
+        //删除最后一个条目之后，光标留在新的最后一个条目上
+        let last_line = self.buffer.borrow().lines.len().saturating_sub(1);
+        self.text_location.line_index = self.text_location.line_index.min(last_line);
+        self.after_contents_replaced();
+        Ok(())
     }
 
     ///处理按键Enter，键入后将当前分为两行
     fn enter(&mut self) {
-        self.buffer.tab(self.text_location);
+        self.buffer.borrow_mut().tab(self.text_location);
         self.set_needs_redraw(true);
     }
     ///处理按键Tab
@@ -151,30 +659,109 @@ impl View {
     ///执行delete,删除光标后面的一位字符
     fn delete(&mut self) {
         //光标位置后面有一位
-        self.buffer.delete(self.text_location);
+        self.buffer.borrow_mut().delete(self.text_location);
         self.set_needs_redraw(true);
     }
 
     ///执行backspace,删除光标前面的一个字符
     fn delete_backward(&mut self) {
+        //光标在一对空的括号或引号之间时，闭合符号一起删除
+        let delete_pair = self.is_inside_empty_pair();
         //光标向前移动一位
         self.handle_move_command(Move::Left);
         self.delete();
+        if delete_pair {
+            self.delete();
+        }
         self.set_needs_redraw(true);
     }
 
+    ///键入字符：开启自动配对时，键入开括号或引号同时在光标后插入闭合符号，键入的闭合符号已经在光标后时跳过它
+    /// 光标后是单词的字符时不配对；引号前是单词的字符(如 don't)时也不配对
+    fn type_char(&mut self, ch: char) {
+        let (previous, next) = self.neighbouring_graphemes();
+        let (auto_pairs, pairs) = {
+            let buffer = self.buffer.borrow();
+            (buffer.options.auto_pairs, buffer.file_type().auto_pairs())
+        };
+        if !auto_pairs {
+            self.insert_char(ch);
+            return;
+        }
+        match Self::pair_action(ch, previous.as_deref(), next.as_deref(), pairs) {
+            PairAction::Insert => self.insert_char(ch),
+            PairAction::Skip => self.handle_move_command(Move::Right),
+            PairAction::InsertPair(close) => {
+                self.insert_char(ch);
+                self.buffer.borrow_mut().insert_char(close, self.text_location);
+            },
+        }
+    }
+
+    ///根据光标前后的字素决定键入 ch 时如何自动配对
+    fn pair_action(ch: char, previous: Option<&str>, next: Option<&str>, pairs: &[(char, char)]) -> PairAction {
+        let is_closer = pairs.iter().any(|(_, close)| *close == ch);
+        if is_closer && next == Some(ch.encode_utf8(&mut [0; 4])) {
+            return PairAction::Skip;
+        }
+        let Some((open, close)) = pairs.iter().find(|(open, _)| *open == ch) else {
+            return PairAction::Insert;
+        };
+        let is_quote = open == close;
+        if Self::is_word(next) || (is_quote && Self::is_word(previous)) {
+            PairAction::Insert
+        } else {
+            PairAction::InsertPair(*close)
+        }
+    }
+
+    ///光标前后的字素
+    fn neighbouring_graphemes(&self) -> (Option<String>, Option<String>) {
+        let Location { line_index, grapheme_index } = self.text_location;
+        let buffer = self.buffer.borrow();
+        let Some(line) = buffer.lines.get(line_index) else {
+            return (None, None);
+        };
+        let previous = grapheme_index.checked_sub(1).and_then(|index| line.grapheme_at(index)).map(str::to_string);
+        (previous, line.grapheme_at(grapheme_index).map(str::to_string))
+    }
+
+    ///开启自动配对时，光标是否在一对空的括号或引号之间，如 `(|)`
+    fn is_inside_empty_pair(&self) -> bool {
+        let (auto_pairs, pairs) = {
+            let buffer = self.buffer.borrow();
+            (buffer.options.auto_pairs, buffer.file_type().auto_pairs())
+        };
+        let (previous, next) = self.neighbouring_graphemes();
+        auto_pairs && Self::is_empty_pair(previous.as_deref(), next.as_deref(), pairs)
+    }
+
+    ///前后的字素是否是一对括号或引号
+    fn is_empty_pair(previous: Option<&str>, next: Option<&str>, pairs: &[(char, char)]) -> bool {
+        pairs.iter().any(|(open, close)| {
+            previous == Some(open.encode_utf8(&mut [0; 4])) && next == Some(close.encode_utf8(&mut [0; 4]))
+        })
+    }
+
+    ///字素是否属于单词：字母、数字或下划线
+    fn is_word(grapheme: Option<&str>) -> bool {
+        grapheme.is_some_and(|grapheme| grapheme.chars().all(|ch| ch.is_alphanumeric() || ch == '_'))
+    }
+
     ///插入字符
     fn insert_char(&mut self,ch: char) {
         let old_len = self
             .buffer
+            .borrow()
             .lines
             .get(self.text_location.line_index)
             .map_or(0, |line| line.grapheme_count());
 
-        self.buffer.insert_char(ch,self.text_location);
+        self.buffer.borrow_mut().insert_char(ch,self.text_location);
 
         let new_len = self
             .buffer
+            .borrow()
             .lines
             .get(self.text_location.line_index)
             .map_or(0,Line::grapheme_count);
@@ -188,8 +775,13 @@ impl View {
 
     ///insert_newline,将一行切割为两行，光标向右移动一位，位于第一行的末尾
     fn insert_newline(&mut self) {
-        self.buffer.insert_newline(self.text_location); //将一行切割为两行
-        self.handle_move_command(Move::Right);//光标向右移动1位，位于第一行的末尾
+        let indent = self.buffer.borrow_mut().insert_newline(self.text_location); //将一行切割为两行
+        //光标位于新的一行的缩进之后
+        self.text_location = Location {
+            line_index: self.text_location.line_index.saturating_add(1),
+            grapheme_index: indent,
+        };
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
     }
 
@@ -218,7 +810,7 @@ impl View {
     ///移动Location,向右移动1个grapheme
     fn move_right(&mut self) {
         //注意这里是统计的grpaheme的个数,向右移动也是以grapheme为单位的。对于像tab这样一个grapheme却可能占几个视觉位置的，光标移动到中间的空位时会直接跳转到下一个grapheme
-        let line_grapheme_len = self.buffer.lines.get(self.text_location.line_index).map_or(0, |v| v.grapheme_count());
+        let line_grapheme_len = self.buffer.borrow().lines.get(self.text_location.line_index).map_or(0, Line::grapheme_count);
         if self.text_location.grapheme_index < line_grapheme_len {
             self.text_location.grapheme_index += 1;
         } else {
@@ -231,6 +823,7 @@ impl View {
     fn move_to_end_of_line(&mut self) {
         self.text_location.grapheme_index = self
             .buffer
+            .borrow()
             .lines
             .get(self.text_location.line_index)
             .map_or(0, |line| { line.grapheme_count() });
@@ -245,6 +838,7 @@ impl View {
    fn snap_to_valid_grapheme(&mut self) {
         self.text_location.grapheme_index = self
             .buffer
+            .borrow()
             .lines
             .get(self.text_location.line_index)
             .map_or(0, |line| {
@@ -253,7 +847,7 @@ impl View {
    }
     /// 位置规范，保证text_location.line_index的位置合法
     fn snap_to_valid_line(&mut self) {
-        self.text_location.line_index = min(self.text_location.line_index,self.buffer.height());
+        self.text_location.line_index = min(self.text_location.line_index,self.buffer.borrow().height());
     }
     ///修改可视范围，使得光标所在行在屏幕的行可视范围内
    fn scroll_vertically(&mut self,to: usize) {
@@ -301,7 +895,7 @@ impl View {
    ///转换location to Position
    fn text_locaton_to_position(&self) -> Position {
         let row = self.text_location.line_index;
-        let column = self.buffer.lines.get(row).map_or(0, |line|
+        let column = self.buffer.borrow().lines.get(row).map_or(0, |line|
             line.width_until(self.text_location.grapheme_index)
         );
         Position { column, row }
@@ -309,19 +903,25 @@ impl View {
     /// 返回渲染在终端屏幕上的的绝对位置。比如屏幕左上角偏移显示的是第20行，text_location是文本的第50行
     /// 则第50行应该渲染在屏幕的第30行
     pub fn caret_position(&self) -> Position {
-        self.text_locaton_to_position().saturating_sub(self.scroll_offset)
+        self.text_locaton_to_position().saturating_sub(self.scroll_offset).saturating_add(self.origin)
+    }
+
+    ///其他view修改同一个buffer之后，保证光标位置仍然合法
+    pub fn snap_to_buffer(&mut self) {
+        self.after_contents_replaced();
     }
     ///将对应路径文件，加载到buffer
-    pub fn load(&mut self, path: &str) -> Result<(),Error> {
-        let buffer = Buffer::load(path)?;
-        self.buffer = buffer;
-        self.set_needs_redraw(true);
+    ///`read_only` 为真时以只读模式打开，否则只有没有写权限的文件才是只读的
+    pub fn load(&mut self, path: &str, settings: &Settings, read_only: bool) -> Result<(),Error> {
+        let mut buffer = Buffer::load(path, settings)?;
+        buffer.options.read_only |= read_only;
+        self.set_buffer(Rc::new(RefCell::new(buffer)));
         Ok(())
     }
 
     ///用于判断是否需要输入文件名
-    pub const fn is_file_loaded(&self) -> bool {
-        self.buffer.is_file_loaded()
+    pub fn is_file_loaded(&self) -> bool {
+        self.buffer.borrow().is_file_loaded()
     }
 
 }
@@ -332,7 +932,7 @@ impl UIComponent for View {
     }
 
     fn needs_redraw(&self) -> bool {
-        self.need_redraw
+        self.need_redraw || self.buffer.borrow().revision() != self.drawn_revision
     }
 
     fn set_size(&mut self,size: Size) {
@@ -345,20 +945,32 @@ impl UIComponent for View {
         let Size {columns,rows} = self.size;
         let end_row = position_row.saturating_add(rows); //保证position_row ~ end_row 之间是一个页面的高度
 
-        let vertical_center = rows/3;
+        let vertical_center = position_row.saturating_add(rows/3);
+        self.drawn_revision = self.buffer.borrow().revision();
+        let partner = self.matching_bracket();
 
         //显示可以显示的行
         for current_row in position_row..end_row {
             let line_idx = current_row.saturating_sub(position_row).saturating_add(self.scroll_offset.row);
 
-            if let Some(line) = self.buffer.lines.get(line_idx) {
+            if let Some(line) = self.buffer.borrow().lines.get(line_idx) {
                 let left = self.scroll_offset.column; //可以显示的文本起始列
                 let right = self.scroll_offset.column.saturating_add(columns);//可以显示的文本终止列的下一列
-                Self::render_line(current_row.saturating_sub(position_row), &line.get_visible_graheme(left..right)); //获取可视范围的文本,渲染
-            } else if current_row == vertical_center && self.buffer.is_empty() {//缓冲区没有内容，需要输出欢迎信息
-                Self::render_line(current_row.saturating_sub(position_row), &Self::build_welcome_message(columns));
+                //与光标处的括号配对的括号和选择一样反色显示
+                let highlighted = self.selected_columns(line_idx, line).or_else(|| {
+                    partner
+                        .filter(|partner| partner.line_index == line_idx)
+                        .map(|partner| line.width_until(partner.grapheme_index)..line.width_until(partner.grapheme_index.saturating_add(1)))
+                });
+                if let Some(selected) = highlighted {
+                    self.render_selected_line(current_row, line, left..right, &selected)?;
+                } else {
+                    self.render_line(current_row, &line.get_visible_graheme(left..right))?; //获取可视范围的文本,渲染
+                }
+            } else if current_row == vertical_center && self.buffer.borrow().is_empty() {//缓冲区没有内容，需要输出欢迎信息
+                self.render_line(current_row, &Self::build_welcome_message(columns))?;
             } else {//输出空行
-                Self::render_line(current_row.saturating_sub(position_row), "~");
+                self.render_line(current_row, "~")?;
             }
         }
 
@@ -366,3 +978,67 @@ impl UIComponent for View {
                  
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('"', '"')];
+
+    #[test]
+    fn openers_insert_their_closer_unless_before_a_word() {
+        assert_eq!(View::pair_action('(', None, None, PAIRS), PairAction::InsertPair(')'));
+        assert_eq!(View::pair_action('(', Some("f"), Some(" "), PAIRS), PairAction::InsertPair(')'));
+        assert_eq!(View::pair_action('[', Some(" "), Some(")"), PAIRS), PairAction::InsertPair(']'));
+        assert_eq!(View::pair_action('(', None, Some("x"), PAIRS), PairAction::Insert);
+        assert_eq!(View::pair_action('(', None, Some("中"), PAIRS), PairAction::Insert);
+    }
+
+    #[test]
+    fn quotes_after_a_word_are_not_paired() {
+        assert_eq!(View::pair_action('"', Some(" "), None, PAIRS), PairAction::InsertPair('"'));
+        assert_eq!(View::pair_action('"', Some("t"), None, PAIRS), PairAction::Insert);
+        assert_eq!(View::pair_action('"', Some("_"), Some(" "), PAIRS), PairAction::Insert);
+    }
+
+    #[test]
+    fn typing_the_next_closer_skips_over_it() {
+        assert_eq!(View::pair_action(')', Some("("), Some(")"), PAIRS), PairAction::Skip);
+        assert_eq!(View::pair_action('"', Some("a"), Some("\""), PAIRS), PairAction::Skip);
+        assert_eq!(View::pair_action(')', Some("("), Some("]"), PAIRS), PairAction::Insert);
+        assert_eq!(View::pair_action('\'', None, None, PAIRS), PairAction::Insert);
+    }
+
+    #[test]
+    fn empty_pairs_are_recognised() {
+        assert!(View::is_empty_pair(Some("("), Some(")"), PAIRS));
+        assert!(View::is_empty_pair(Some("\""), Some("\""), PAIRS));
+        assert!(!View::is_empty_pair(Some("("), Some("]"), PAIRS));
+        assert!(!View::is_empty_pair(None, Some(")"), PAIRS));
+    }
+
+    #[test]
+    fn tab_in_leading_indent_indents_the_line() {
+        let line = Line::from("    let x = 1;");
+        assert_eq!(View::tab_text(&line, 0, Indentation::Spaces(4)), None);
+        assert_eq!(View::tab_text(&line, 2, Indentation::Spaces(4)), None);
+        assert_eq!(View::tab_text(&line, 4, Indentation::Tabs), None);
+        assert_eq!(View::tab_text(&Line::from("\tx"), 1, Indentation::Tabs), None);
+    }
+
+    #[test]
+    fn tab_after_text_inserts_up_to_the_next_tab_stop() {
+        let line = Line::from("ab = 1;");
+        assert_eq!(View::tab_text(&line, 2, Indentation::Spaces(4)), Some(String::from("  ")));
+        assert_eq!(View::tab_text(&line, 4, Indentation::Spaces(4)), Some(String::from("    ")));
+        assert_eq!(View::tab_text(&Line::from("中文"), 1, Indentation::Spaces(4)), Some(String::from("  ")));
+        assert_eq!(View::tab_text(&line, 2, Indentation::Tabs), Some(String::from("\t")));
+    }
+
+    #[test]
+    fn tab_on_a_blank_line_inserts_indentation() {
+        assert_eq!(View::tab_text(&Line::default(), 0, Indentation::Spaces(4)), Some(String::from("    ")));
+        assert_eq!(View::tab_text(&Line::from("  "), 2, Indentation::Spaces(4)), Some(String::from("  ")));
+        assert_eq!(View::tab_text(&Line::default(), 0, Indentation::Tabs), Some(String::from("\t")));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Error, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+const MAX_SYMLINK_DEPTH: usize = 40; //跟随符号链接的最大层数，避免循环的链接
+const NEW_FILE_MODE: u32 = 0o666; //新建普通文件的权限，与其他程序一样再受 umask 限制
+const PRIVATE_FILE_MODE: u32 = 0o600; //新建私有文件的权限
+
+///以原子的方式写入文件：先写入同一目录下的临时文件并同步到磁盘，再重命名覆盖目标文件
+/// 目标是符号链接时写入其指向的文件；原文件的权限会被保留；任何一步出错，原文件都保持不变
+/// 目录不可写时无法创建临时文件，直接返回错误，不会退而覆盖写入原文件
+pub fn write(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    write_with_mode(path, bytes, NEW_FILE_MODE)
+}
+
+///与 `write` 相同，但新建的文件只有属主可以读写，用于交换文件等可能含有他人文件内容的文件
+pub fn write_private(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    write_with_mode(path, bytes, PRIVATE_FILE_MODE)
+}
+
+///`new_mode` 只用于目标文件不存在的情况，已存在时沿用其权限
+fn write_with_mode(path: &Path, bytes: &[u8], new_mode: u32) -> Result<(), Error> {
+    let target = resolve_symlink(path);
+    let temp_path = temp_path_for(&target);
+
+    let result = write_temp(&target, &temp_path, bytes, new_mode)
+        .and_then(|()| fs::rename(&temp_path, &target));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    sync_directory(&target);
+    Ok(())
+}
+
+///跟随符号链接找到真正的文件，相对的链接以链接所在的目录为基准
+/// 链接指向的文件不存在时返回其指向的路径，写入时创建该文件而不是用普通文件替换链接
+fn resolve_symlink(path: &Path) -> PathBuf {
+    let mut target = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        let Ok(link) = fs::read_link(&target) else {
+            break;
+        };
+        target = match target.parent() {
+            Some(parent) if link.is_relative() => parent.join(link),
+            _ => link,
+        };
+    }
+    target
+}
+
+///临时文件与目标文件位于同一目录，保证重命名不会跨文件系统
+fn temp_path_for(target: &Path) -> PathBuf {
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{file_name}.hecto-tmp-{}", process::id()))
+}
+
+///写入临时文件并同步到磁盘；临时文件在创建时就带上原文件的权限，写入内容之前不会被他人读取
+fn write_temp(target: &Path, temp_path: &Path, bytes: &[u8], new_mode: u32) -> Result<(), Error> {
+    let metadata = fs::metadata(target).ok();
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+        options.mode(metadata.as_ref().map_or(new_mode, |metadata| metadata.mode() & 0o7777));
+    }
+    #[cfg(not(unix))]
+    let _ = new_mode;
+    let mut file = options.open(temp_path)?;
+
+    if let Some(metadata) = &metadata {
+        //创建时的权限受 umask 影响，再明确设置一次
+        file.set_permissions(metadata.permissions())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{MetadataExt, fchown};
+            //只有有权限时才能保留属主，失败时忽略
+            let _ = fchown(&file, Some(metadata.uid()), Some(metadata.gid()));
+        }
+    }
+
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+///同步目录，保证重命名操作本身也落盘；并非所有平台都支持，失败时忽略
+fn sync_directory(target: &Path) {
+    if let Some(dir) = target.parent().filter(|dir| !dir.as_os_str().is_empty())
+        && let Ok(dir) = File::open(dir)
+    {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::testdir::TestDir;
+
+    #[test]
+    fn replaces_contents_and_leaves_no_temp_file() {
+        let dir = TestDir::new("atomicwrite-replace");
+        let path = dir.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        write(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_relative_symlink_chains() {
+        use std::os::unix::fs::symlink;
+        let dir = TestDir::new("atomicwrite-chain");
+        fs::create_dir(dir.join("real")).unwrap();
+        fs::write(dir.join("real/file.txt"), "old").unwrap();
+        symlink("real/file.txt", dir.join("first")).unwrap();
+        symlink("first", dir.join("second")).unwrap();
+
+        write(&dir.join("second"), b"new").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("real/file.txt")).unwrap(), "new");
+        assert!(fs::symlink_metadata(dir.join("second")).unwrap().file_type().is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlink_creates_its_target() {
+        use std::os::unix::fs::symlink;
+        let dir = TestDir::new("atomicwrite-dangling");
+        symlink("missing.txt", dir.join("link")).unwrap();
+
+        write(&dir.join("link"), b"created").unwrap();
+        assert!(fs::symlink_metadata(dir.join("link")).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(dir.join("missing.txt")).unwrap(), "created");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fails_without_touching_the_file_when_directory_is_not_writable() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TestDir::new("atomicwrite-readonly-dir");
+        let path = dir.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        //root 不受目录权限限制，此时无法模拟这种情况
+        let restricted = File::create(dir.join("probe")).is_err();
+
+        let result = write(&path, b"new");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        if restricted {
+            assert!(result.is_err());
+            assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+            assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_existing_permissions_and_creates_private_files() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TestDir::new("atomicwrite-mode");
+        let path = dir.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        write(&path, b"new").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+
+        let private = dir.join("private.txt");
+        write_private(&private, b"secret").unwrap();
+        assert_eq!(fs::metadata(&private).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::editor::settings::{BackupMode, Settings};
+use std::{
+    ffi::OsString,
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+///保存前备份磁盘上的文件，保留最近 `backup_keep` 份
+/// 最新的一份为 `name~`，较旧的依次为 `name~1~`、`name~2~`……
+pub fn backup(path: &Path, settings: &Settings) -> Result<(), Error> {
+    if settings.backup == BackupMode::Off || !path.is_file() {
+        return Ok(());
+    }
+    if settings.backup == BackupMode::Central {
+        fs::create_dir_all(&settings.backup_dir)?;
+    }
+
+    let base = backup_base(path, settings.backup, &settings.backup_dir);
+    let keep = settings.backup_keep.max(1);
+
+    //删除超出保留数量的旧备份，再将其余备份依次后移一位
+    let mut index = keep.saturating_sub(1);
+    while version(&base, index).exists() {
+        fs::remove_file(version(&base, index))?;
+        index = index.saturating_add(1);
+    }
+    for index in (0..keep.saturating_sub(1)).rev() {
+        match fs::rename(version(&base, index), version(&base, index.saturating_add(1))) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    fs::copy(path, version(&base, 0))?;
+    Ok(())
+}
+
+///文件最新的一份备份；备份关闭时依次查找文件旁边和备份目录中的备份
+pub fn newest(path: &Path, settings: &Settings) -> Option<PathBuf> {
+    let modes = match settings.backup {
+        BackupMode::Off => vec![BackupMode::Local, BackupMode::Central],
+        mode => vec![mode],
+    };
+    modes
+        .into_iter()
+        .map(|mode| version(&backup_base(path, mode, &settings.backup_dir), 0))
+        .find(|backup| backup.is_file())
+}
+
+///备份文件名去掉 `~` 后缀的部分
+/// 统一备份目录中，使用完整路径并将 `/` 替换为 `%` 作为文件名，避免不同目录下的同名文件冲突
+fn backup_base(path: &Path, mode: BackupMode, backup_dir: &Path) -> PathBuf {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if mode == BackupMode::Central {
+        let mangled = path.to_string_lossy().replace(['/', '\\'], "%");
+        backup_dir.join(mangled)
+    } else {
+        path
+    }
+}
+
+fn version(base: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(base.as_os_str());
+    if index == 0 {
+        name.push("~");
+    } else {
+        name.push(format!("~{index}~"));
+    }
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::testdir::TestDir;
+
+
+    #[test]
+    fn local_backups_rotate_and_keep_the_configured_count() {
+        let dir = TestDir::new("backup-local");
+        let path = dir.join("file.txt");
+        let settings = Settings {
+            backup: BackupMode::Local,
+            backup_keep: 2,
+            ..Settings::default()
+        };
+        for contents in ["first", "second", "third"] {
+            fs::write(&path, contents).unwrap();
+            backup(&path, &settings).unwrap();
+        }
+        assert_eq!(fs::read_to_string(dir.join("file.txt~")).unwrap(), "third");
+        assert_eq!(fs::read_to_string(dir.join("file.txt~1~")).unwrap(), "second");
+        assert!(!dir.join("file.txt~2~").exists());
+        assert_eq!(newest(&path, &settings), Some(dir.join("file.txt~")));
+    }
+
+    #[test]
+    fn central_backups_use_the_mangled_full_path() {
+        let dir = TestDir::new("backup-central");
+        let path = dir.join("file.txt");
+        fs::write(&path, "contents").unwrap();
+        let settings = Settings {
+            backup: BackupMode::Central,
+            backup_dir: dir.join("backups"),
+            ..Settings::default()
+        };
+        backup(&path, &settings).unwrap();
+        let expected = dir.join("backups").join(format!("{}~", path.to_string_lossy().replace('/', "%")));
+        assert_eq!(fs::read_to_string(&expected).unwrap(), "contents");
+        //备份关闭时也能找到已有的备份
+        let off = Settings { backup_dir: dir.join("backups"), ..Settings::default() };
+        assert_eq!(newest(&path, &off), Some(expected));
+    }
+
+    #[test]
+    fn no_backup_when_off_or_file_missing() {
+        let dir = TestDir::new("backup-off");
+        let path = dir.join("file.txt");
+        fs::write(&path, "contents").unwrap();
+        backup(&path, &Settings::default()).unwrap();
+        let local = Settings { backup: BackupMode::Local, ..Settings::default() };
+        backup(&dir.join("missing.txt"), &local).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -1,17 +1,76 @@
 use core::default::Default;
-use std::fs::{read_to_string,File};
-use std::io::Error;
-use std::io::Write;
+use std::fs;
+use std::io::{Error,ErrorKind};
+use std::ops::RangeInclusive;
+use std::path::{Path,PathBuf};
 use super::Location;
-use crate::editor::line::Line;
+use super::atomicwrite;
+use super::backup;
+use super::swap::{self,SwapState};
+use crate::editor::line::{Line,TAB_WIDTH};
+use crate::editor::lineending::{LineEnding,LineEndingStyle};
+use crate::editor::encoding::FileEncoding;
+use crate::editor::settings::Settings;
+use crate::editor::filetype::FileType;
+use crate::editor::indentation::Indentation;
 
-use crate::editor::view::fileinfo::FileInfo;
+use crate::editor::view::fileinfo::{DiskChange,FileInfo};
+use unicode_segmentation::UnicodeSegmentation;
+
+///buffer显示的内容的种类
+#[derive(Default, Clone, Copy, Eq, PartialEq)]
+pub enum BufferKind {
+    #[default]
+    File, //普通文件
+    Directory, //目录的列表，只读，每一行是一个条目
+    SearchResults, //搜索的结果，只读，每一行是一处匹配
+}
+
+const PARENT_ENTRY: &str = "../"; //目录列表中表示上一级目录的条目
+const MAX_BRACKET_SEARCH_LINES: usize = 1000; //查找配对的括号时最多向前或向后查看的行数
+const BRACKETS: [(&str, &str); 3] = [("(", ")"), ("[", "]"), ("{", "}")];
+
+///每个buffer各自的编辑选项，新buffer的选项来自设置
+#[derive(Default, Clone, Copy)]
+pub struct BufferOptions {
+    pub read_only: bool, //只读模式下拒绝所有修改
+    pub indentation: Indentation, //Tab键和自动缩进使用的缩进方式
+    pub auto_pairs: bool, //键入括号和引号时是否自动插入闭合符号
+}
+
+impl BufferOptions {
+    ///设置中对某种文件类型的选项
+    pub fn from_settings(settings: &Settings, file_type: FileType) -> Self {
+        Self {
+            read_only: false,
+            indentation: Indentation::from_settings(settings.expandtab, settings.indent_width),
+            auto_pairs: settings.autopairs.get(file_type),
+        }
+    }
+}
+
+///撤销时恢复的内容
+struct Snapshot {
+    lines: Vec<String>,
+    line_endings: Vec<LineEnding>,
+    no_final_newline: bool,
+}
 
 #[derive(Default)]
 pub struct Buffer {
     pub lines: Vec<Line>,
     pub file_info: FileInfo,//表示显示的文件的路径
     pub dirty: bool, //修改位，表示是否修改
+    pub is_new_file: bool, //文件在磁盘上尚不存在，第一次保存时创建
+    line_endings: Vec<LineEnding>, //每一行末尾的换行符，与lines一一对应
+    pub no_final_newline: bool, //文件的最后一行是否没有换行符
+    revision: u64, //每次修改加一，用于判断交换文件是否需要更新
+    pub swap: SwapState, //交换文件的写入和检查状态
+    pub last_location: Location, //切换到其他buffer时的光标位置，切换回来时恢复
+    pub kind: BufferKind, //显示的是文件、目录列表还是搜索结果
+    targets: Vec<(PathBuf, Location)>, //搜索结果中每一行对应的文件和位置
+    pub options: BufferOptions, //只读、缩进方式等编辑选项
+    undo_snapshot: Option<Snapshot>, //保存时整理内容之前的内容，之后再有修改时丢弃
 }
 
 
@@ -23,19 +82,337 @@ impl Buffer {
    } 
 
    ///每次传入文件都是全新的buffer
-   pub fn load(file_name: &str) -> Result<Self,Error> {
-        let contents = read_to_string(file_name)?; 
-        let mut content_lines = Vec::new();
+   ///文件不存在时，返回绑定了该路径的空buffer
+   pub fn load(file_name: &str, settings: &Settings) -> Result<Self,Error> {
+        if Path::new(file_name).is_dir() {
+            let mut buffer = Self::default();
+            buffer.load_directory(Path::new(file_name))?;
+            return Ok(buffer);
+        }
+        let mut buffer = Self {
+            file_info: FileInfo::from(file_name),
+            options: BufferOptions::from_settings(settings, FileType::from_path(Some(Path::new(file_name)))),
+            ..Self::default()
+        };
+        let bytes = match fs::read(file_name) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                buffer.is_new_file = true;
+                return Ok(buffer);
+            },
+            Err(err) => return Err(err),
+        };
+
+        //识别文件编码，保存时使用相同的编码写回
+        buffer.set_file_contents(&bytes, FileEncoding::detect(&bytes, settings.legacy_encoding));
+        //沿用文件中占多数的缩进方式
+        if let Some(indentation) = Indentation::detect(&buffer.lines) {
+            buffer.options.indentation = indentation;
+        }
+        //没有写权限的文件，以及有无法解码的字节的文件以只读模式打开，避免保存时丢失原来的字节
+        buffer.options.read_only = buffer.file_info.is_lossy() || !buffer.file_info.is_writable();
+        Ok(buffer)
+    }
+
+    ///用磁盘上的文件内容替换所有行，记录编码以及文件的状态
+    fn set_file_contents(&mut self, bytes: &[u8], encoding: FileEncoding) {
+        let (contents, lossy) = encoding.decode(bytes);
+        self.set_contents(&contents);
+        self.file_info.set_encoding(encoding);
+        self.file_info.set_lossy(lossy);
+        self.file_info.record_disk_state(bytes);
+    }
+
+    ///用目录的列表替换内容：第一行为 ../，之后是以 / 结尾的子目录，最后是文件，各自按名称排序
+    /// 目录列表是只读的，不会保存到磁盘
+    pub fn load_directory(&mut self, path: &Path) -> Result<(),Error> {
+        let path = fs::canonicalize(path)?;
+        let mut directories = Vec::new();
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if swap::is_swap_file(&name) {
+                continue;
+            }
+            if entry.path().is_dir() {
+                directories.push(format!("{name}/"));
+            } else {
+                files.push(name);
+            }
+        }
+        directories.sort();
+        files.sort();
 
-        for line in contents.lines() {
-           content_lines.push(Line::from(line)); 
+        let mut contents = String::new();
+        if path.parent().is_some() {
+            contents.push_str(PARENT_ENTRY);
+            contents.push('\n');
+        }
+        for name in directories.iter().chain(files.iter()) {
+            contents.push_str(name);
+            contents.push('\n');
+        }
+        self.set_contents(&contents);
+        self.file_info = FileInfo::from_path(&path);
+        self.kind = BufferKind::Directory;
+        self.options.read_only = true;
+        self.dirty = false;
+        self.is_new_file = false;
+        Ok(())
+    }
+
+    ///目录列表中第 `line_index` 行的条目对应的路径，../ 对应上一级目录
+    pub fn directory_entry(&self, line_index: usize) -> Option<PathBuf> {
+        if self.kind != BufferKind::Directory {
+            return None;
+        }
+        let directory = self.file_info.get_path()?;
+        let entry = self.lines.get(line_index)?.to_string();
+        if entry == PARENT_ENTRY {
+            return directory.parent().map(Path::to_path_buf);
+        }
+        Some(directory.join(entry.trim_end_matches('/')))
+    }
+
+    ///以指定的编码重新读取当前文件，放弃未保存的修改
+    pub fn reload_with_encoding(&mut self, encoding: FileEncoding) -> Result<(),Error> {
+        let Some(path) = self.file_info.get_path() else {
+            return Err(Error::new(ErrorKind::NotFound, "buffer has no file"));
+        };
+        let bytes = fs::read(path)?;
+        let was_lossy = self.file_info.is_lossy();
+        self.set_file_contents(&bytes, encoding);
+        //换用正确的编码之后，恢复因为无法解码而设置的只读
+        if self.file_info.is_lossy() {
+            self.options.read_only = true;
+        } else if was_lossy {
+            self.options.read_only = !self.file_info.is_writable();
+        }
+        self.dirty = false;
+        self.is_new_file = false;
+        Ok(())
+    }
+
+    ///检查磁盘上的文件是否被其他程序修改或删除
+    /// 文件被删除时视为新文件，保存时重新创建，只提示一次
+    pub fn check_disk(&mut self) -> DiskChange {
+        let change = self.file_info.check_disk_state();
+        if change == DiskChange::Removed {
+            self.file_info.acknowledge_disk_state();
+            self.is_new_file = true;
+        }
+        change
+    }
+
+    ///自动保存时是否需要保存：有修改、可写、有文件名的普通文件
+    pub fn needs_autosave(&self) -> bool {
+        self.dirty && !self.options.read_only && self.kind == BufferKind::File && self.is_file_loaded()
+    }
+
+    ///保留当前内容，不再提示磁盘上的这次修改
+    pub fn acknowledge_disk_change(&mut self) {
+        self.file_info.acknowledge_disk_state();
+    }
+
+    ///指定保存时使用的编码
+    pub fn set_encoding(&mut self, encoding: FileEncoding) {
+        if self.file_info.get_encoding() != encoding {
+            self.file_info.set_encoding(encoding);
+            self.mark_modified();
+        }
+    }
+
+    ///用解码后的文件内容替换所有行，并记录每一行的换行符
+    fn set_contents(&mut self, contents: &str) {
+        self.revision = self.revision.wrapping_add(1);
+        self.lines.clear();
+        self.line_endings.clear();
+        self.no_final_newline = false;
+        self.undo_snapshot = None;
+
+        //split_inclusive 保留每一行的换行符，用于记录换行风格
+        for line in contents.split_inclusive('\n') {
+            let (text, ending) = LineEnding::split(line);
+            self.lines.push(Line::from(text)); 
+            if let Some(ending) = ending {
+                self.line_endings.push(ending);
+            } else {
+                //最后一行没有换行符，沿用前一行的换行符，便于之后在其后插入新行
+                self.no_final_newline = true;
+                self.line_endings.push(self.line_endings.last().copied().unwrap_or_default());
+            }
         }        
-        Ok(
-            Self { 
-                lines: content_lines,
-                file_info:FileInfo::from(file_name), 
-                dirty: false
-         })
+    }
+
+    ///内容每次变化都会增加，用于判断显示同一个buffer的view是否需要重新渲染
+    pub const fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    ///标记内容被修改
+    fn mark_modified(&mut self) {
+        self.dirty = true;
+        self.revision = self.revision.wrapping_add(1);
+        //只能撤销最近一次的整理，之后的修改会使其失效
+        self.undo_snapshot = None;
+    }
+
+    ///撤销保存时对内容的整理，恢复之后内容与磁盘上不同，视为有未保存的修改；没有可以撤销的内容时返回 false
+    pub fn undo_save_hooks(&mut self) -> bool {
+        if !self.restore_snapshot() {
+            return false;
+        }
+        self.mark_modified();
+        true
+    }
+
+    ///恢复整理之前的内容并丢弃记录，不改变是否有未保存的修改；没有记录时返回 false
+    fn restore_snapshot(&mut self) -> bool {
+        let Some(snapshot) = self.undo_snapshot.take() else {
+            return false;
+        };
+        self.lines = snapshot.lines.iter().map(|line| Line::from(line)).collect();
+        self.line_endings = snapshot.line_endings;
+        self.no_final_newline = snapshot.no_final_newline;
+        self.revision = self.revision.wrapping_add(1);
+        true
+    }
+
+    ///按照文件类型的设置在保存前整理内容：删除行末的空白、删除文件末尾的空行、保证以一个换行符结尾
+    /// 内容有变化时记录整理之前的内容，可以用 undosave 撤销；没有需要整理的内容时不复制内容，返回 false
+    fn apply_save_hooks(&mut self, file_type: FileType, settings: &Settings) -> bool {
+        let has_trailing_blank_line = |lines: &[Line]| lines.len() > 1 && lines.last().is_some_and(|line| line.to_string().trim().is_empty());
+        let trim_whitespace = settings.trim_trailing_whitespace.get(file_type)
+            && self.lines.iter().any(|line| line.to_string().ends_with(char::is_whitespace));
+        let trim_blank_lines = settings.trim_trailing_blank_lines.get(file_type) && has_trailing_blank_line(&self.lines);
+        let add_final_newline = settings.final_newline.get(file_type) && self.no_final_newline;
+        if !trim_whitespace && !trim_blank_lines && !add_final_newline {
+            return false;
+        }
+
+        let snapshot = Snapshot {
+            lines: self.text_lines(),
+            line_endings: self.line_endings.clone(),
+            no_final_newline: self.no_final_newline,
+        };
+        if trim_whitespace {
+            for line in &mut self.lines {
+                let text = line.to_string();
+                if text.trim_end().len() < text.len() {
+                    *line = Line::from(text.trim_end());
+                }
+            }
+        }
+        if trim_blank_lines {
+            while has_trailing_blank_line(&self.lines) {
+                self.lines.pop();
+                self.line_endings.pop();
+            }
+        }
+        if add_final_newline {
+            //最后一行为空时，前一行的换行符已经是文件结尾的换行符，去掉这个空行，否则在最后一行之后加上换行符
+            if self.lines.len() > 1 && self.lines.last().is_some_and(|line| line.grapheme_count() == 0) {
+                self.lines.pop();
+                self.line_endings.pop();
+            }
+            self.no_final_newline = false;
+        }
+        self.revision = self.revision.wrapping_add(1);
+        self.undo_snapshot = Some(snapshot);
+        true
+    }
+
+    ///整理内容后保存；保存失败时恢复整理之前的内容，buffer 与保存之前完全相同
+    fn save_with_hooks(
+        &mut self,
+        file_type: FileType,
+        settings: &Settings,
+        save: impl FnOnce(&mut Self) -> Result<(),Error>,
+    ) -> Result<(),Error> {
+        let tidied = self.apply_save_hooks(file_type, settings);
+        let result = save(self);
+        if result.is_err() && tidied {
+            self.restore_snapshot();
+        }
+        result
+    }
+
+    ///有未保存的修改时，将内容写入交换文件(UTF-8)，内容没有变化时不重复写入；没有修改时删除自己写过的交换文件
+    pub fn update_swap(&mut self) -> Result<(),Error> {
+        let Some(path) = self.file_info.get_path() else {
+            return Ok(());
+        };
+        if self.swap.suspended {
+            return Ok(());
+        }
+        if !self.dirty {
+            return self.remove_swap();
+        }
+        if self.swap.written_revision != Some(self.revision) {
+            atomicwrite::write_private(&swap::swap_path(path), self.contents().as_bytes())?;
+            self.swap.written_revision = Some(self.revision);
+        }
+        Ok(())
+    }
+
+    ///删除自己写过的交换文件，其他进程留下的交换文件不受影响
+    pub fn remove_swap(&mut self) -> Result<(),Error> {
+        if self.swap.written_revision.take().is_some()
+            && let Some(path) = self.file_info.get_path()
+        {
+            match fs::remove_file(swap::swap_path(path)) {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    ///上一次编辑异常结束时留下的交换文件
+    pub fn pending_swap(&self) -> Option<PathBuf> {
+        self.file_info.get_path().and_then(swap::pending_recovery)
+    }
+
+    ///文件的换行风格
+    pub fn line_ending_style(&self) -> LineEndingStyle {
+        LineEndingStyle::detect(&self.line_endings)
+    }
+
+    ///新插入的行使用的换行符：混合风格时取出现次数较多的一种
+    fn default_line_ending(&self) -> LineEnding {
+        let crlf_count = self.line_endings.iter().filter(|ending| **ending == LineEnding::Crlf).count();
+        if crlf_count.saturating_mul(2) > self.line_endings.len() {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    ///将所有行的换行符统一转换为指定的风格
+    pub fn convert_line_endings(&mut self, ending: LineEnding) {
+        if self.line_endings.iter().any(|current| *current != ending) {
+            self.line_endings.fill(ending);
+            self.mark_modified();
+        }
+    }
+
+    ///在指定位置插入一行，同时记录其换行符
+    fn insert_line(&mut self, line_index: usize, line: Line, ending: LineEnding) {
+        self.lines.insert(line_index, line);
+        self.line_endings.insert(line_index, ending);
+    }
+
+    ///将指定行与下一行合并，合并后的行沿用下一行的换行符
+    fn join_with_next_line(&mut self, line_index: usize) {
+        let next_index = line_index.saturating_add(1);
+        if next_index >= self.height() {
+            return;
+        }
+        let next_line = self.lines.remove(next_index);
+        let next_ending = self.line_endings.remove(next_index);
+        self.lines[line_index].append(&next_line);
+        self.line_endings[line_index] = next_ending;
     }
 
     ///当前是否有文件已经加载
@@ -54,40 +431,234 @@ impl Buffer {
             return;
         }
         if at.line_index == self.height() {
-            self.lines.push(Line::from(&character.to_string()));
-            self.dirty = true;
+            let ending = self.default_line_ending();
+            self.insert_line(at.line_index, Line::from(&character.to_string()), ending);
+            self.mark_modified();
         } else if let Some(line) = self.lines.get_mut(at.line_index) {
             line.insert_char(character,at.grapheme_index);
-            self.dirty = true;
+            self.mark_modified();
         }
 
     }
 
     ///插入新的一行,若插入位置在最后一行，则直接加入空白行
     /// 若插入位置已经有信息，则将该行分成两行，并将信息插入第二行的末尾
-    pub fn insert_newline(&mut self,at: Location) {
+    /// 新的一行沿用当前行开头的空白，行末是文件类型的开括号等字符时再多缩进一级
+    /// 光标后的内容已经以空白开头时，只补上它缺少的缩进，避免在行首的空白中换行时缩进加倍
+    /// 只有空白的行上换行时，清除该行的缩进；返回新的一行中光标之前插入的字素数量，用于放置光标
+    pub fn insert_newline(&mut self,at: Location) -> usize {
         if at.line_index == self.height() {
-            self.lines.push(Line::default());
-            self.dirty = true;
-        } else if let Some(line) = self.lines.get_mut(at.line_index) {
-            let new = line.split_off(at.grapheme_index);
-            self.lines.insert(at.line_index.saturating_add(1), new);
-            self.dirty = true;
+            let ending = self.default_line_ending();
+            self.insert_line(at.line_index, Line::default(), ending);
+            self.mark_modified();
+            return 0;
+        }
+        let openers = self.file_type().indent_openers();
+        let Some(line) = self.lines.get_mut(at.line_index) else {
+            return 0;
+        };
+        let rest = line.split_off(at.grapheme_index).to_string();
+        let before = line.to_string();
+        let mut indent: String = before.chars().take_while(|ch| matches!(ch, ' ' | '\t')).collect();
+        let new = if before.trim().is_empty() && rest.trim().is_empty() {
+            *line = Line::default();
+            Line::from(&indent)
+        } else {
+            if before.trim_end().ends_with(openers) {
+                indent.push_str(&self.options.indentation.unit());
+            }
+            //indent 只含空格和Tab，按字节截取是安全的
+            let supplied = rest.len().saturating_sub(rest.trim_start_matches([' ', '\t']).len());
+            indent = indent.split_off(supplied.min(indent.len()));
+            Line::from(&format!("{indent}{rest}"))
+        };
+        let ending = self.line_endings[at.line_index];
+        self.insert_line(at.line_index.saturating_add(1), new, ending);
+        self.mark_modified();
+        indent.chars().count()
+    }
+
+    ///整个文件在Tab与空格之间转换，保持对齐：转为空格时替换所有的Tab，转为Tab时只转换行首的缩进
+    /// 之后的缩进也使用转换后的方式，转为空格时缩进宽度取转换后的内容中判断出的宽度，无法判断时使用 width
+    /// 返回内容是否发生了变化
+    pub fn retab(&mut self, expand: bool, width: usize) -> bool {
+        let mut changed = false;
+        for line in &mut self.lines {
+            let converted = if expand { line.expand_tabs() } else { line.tabify_indent() };
+            if converted.to_string() != line.to_string() {
+                *line = converted;
+                changed = true;
+            }
+        }
+        self.options.indentation = if expand {
+            match Indentation::detect(&self.lines) {
+                Some(Indentation::Spaces(detected)) => Indentation::Spaces(detected),
+                _ => Indentation::Spaces(width.max(1)),
+            }
+        } else {
+            Indentation::Tabs
+        };
+        if changed {
+            self.mark_modified();
+        }
+        changed
+    }
+
+    ///给范围内的每一行增加一级缩进，空行保持不变；返回每一行增加的字素数量
+    pub fn indent_lines(&mut self, lines: RangeInclusive<usize>) -> Vec<usize> {
+        let unit = self.options.indentation.unit();
+        let mut added = Vec::new();
+        for index in lines {
+            match self.lines.get_mut(index) {
+                Some(line) if line.grapheme_count() > 0 => {
+                    *line = Line::from(&format!("{unit}{line}"));
+                    added.push(unit.len());
+                },
+                _ => added.push(0),
+            }
+        }
+        if added.iter().any(|count| *count > 0) {
+            self.mark_modified();
+        }
+        added
+    }
+
+    ///范围内的每一行减少一级缩进：去掉行首的一个Tab，或者至多一级缩进宽度的空格；返回每一行删除的字素数量
+    pub fn dedent_lines(&mut self, lines: RangeInclusive<usize>) -> Vec<usize> {
+        let width = match self.options.indentation {
+            Indentation::Tabs => TAB_WIDTH,
+            Indentation::Spaces(width) => width,
+        };
+        let mut removed = Vec::new();
+        for index in lines {
+            let Some(line) = self.lines.get_mut(index) else {
+                removed.push(0);
+                continue;
+            };
+            let text = line.to_string();
+            let count = if text.starts_with('\t') {
+                1
+            } else {
+                text.chars().take(width).take_while(|&ch| ch == ' ').count()
+            };
+            if count > 0 {
+                *line = Line::from(&text[count..]);
+            }
+            removed.push(count);
+        }
+        if removed.iter().any(|count| *count > 0) {
+            self.mark_modified();
+        }
+        removed
+    }
+
+    ///切换若干行的注释：所有非空行都已注释时去掉注释，否则在这些行的最小缩进处加上注释，空行不变
+    /// 缩进只计算行首的空格和Tab，它们都是单字节的字素，因此字节位置与字素位置相同
+    /// 返回是否加上了注释，以及每一行注释开头所在的字素位置和长度，未修改的行为 None
+    pub fn toggle_comment(&mut self, lines: RangeInclusive<usize>, (open, close): (&str, &str)) -> (bool, Vec<Option<(usize, usize)>>) {
+        let first = *lines.start();
+        let texts: Vec<Option<String>> = lines
+            .map(|index| self.lines.get(index).map(Line::to_string).filter(|text| !text.trim().is_empty()))
+            .collect();
+        let leading = |text: &str| text.len().saturating_sub(text.trim_start_matches([' ', '\t']).len());
+        let is_commented = |text: &String| {
+            let body = text[leading(text)..].trim_end();
+            body.len() >= open.len().saturating_add(close.len()) && body.starts_with(open) && body.ends_with(close)
+        };
+        let comment = !texts.iter().flatten().all(is_commented);
+        let indent = texts.iter().flatten().map(|text| leading(text)).min().unwrap_or(0);
+        let open_graphemes = open.graphemes(true).count();
+
+        let mut changes = Vec::new();
+        for (index, text) in texts.iter().enumerate() {
+            let Some(text) = text else {
+                changes.push(None);
+                continue;
+            };
+            let (new_text, change) = if comment {
+                let suffix = if close.is_empty() { String::new() } else { format!(" {close}") };
+                (format!("{}{open} {}{suffix}", &text[..indent], &text[indent..]), (indent, open_graphemes.saturating_add(1)))
+            } else {
+                //注释后面的一个空格以及结尾前面的一个空格是加注释时插入的，一起去掉
+                let start = leading(text);
+                let rest = &text[start.saturating_add(open.len())..];
+                let (rest, prefix) = rest.strip_prefix(' ').map_or((rest, open_graphemes), |rest| (rest, open_graphemes.saturating_add(1)));
+                let body = rest.trim_end();
+                let trailing = &rest[body.len()..];
+                let body = body.strip_suffix(close).unwrap_or(body);
+                let body = if close.is_empty() { body } else { body.strip_suffix(' ').unwrap_or(body) };
+                (format!("{}{body}{trailing}", &text[..start]), (start, prefix))
+            };
+            if let Some(line) = self.lines.get_mut(first.saturating_add(index)) {
+                *line = Line::from(&new_text);
+            }
+            changes.push(Some(change));
+        }
+        if changes.iter().any(Option::is_some) {
+            self.mark_modified();
+        }
+        (comment, changes)
+    }
+
+    ///与 at 处的括号配对的括号的位置，at 处不是括号或者在限定的行数内没有找到时返回 None
+    /// 目前没有语法高亮，无法区分字符串和注释，其中的括号同样参与配对
+    pub fn matching_bracket(&self, at: Location) -> Option<Location> {
+        let grapheme = self.lines.get(at.line_index)?.grapheme_at(at.grapheme_index)?;
+        let (same, other, forward) = BRACKETS.iter().find_map(|&(open, close)| {
+            if grapheme == open {
+                Some((open, close, true))
+            } else if grapheme == close {
+                Some((close, open, false))
+            } else {
+                None
+            }
+        })?;
+        //从 at 处开始计数，深度回到零的位置就是配对的括号
+        let mut depth = 0_usize;
+        let mut is_match = |line: &Line, grapheme_index: usize| {
+            let grapheme = line.grapheme_at(grapheme_index);
+            if grapheme == Some(same) {
+                depth = depth.saturating_add(1);
+            } else if grapheme == Some(other) {
+                depth = depth.saturating_sub(1);
+                return depth == 0;
+            }
+            false
+        };
+        if forward {
+            self.lines.iter().enumerate().skip(at.line_index).take(MAX_BRACKET_SEARCH_LINES).find_map(|(line_index, line)| {
+                let start = if line_index == at.line_index { at.grapheme_index } else { 0 };
+                (start..line.grapheme_count())
+                    .find(|&grapheme_index| is_match(line, grapheme_index))
+                    .map(|grapheme_index| Location { grapheme_index, line_index })
+            })
+        } else {
+            self.lines.iter().enumerate().take(at.line_index.saturating_add(1)).rev().take(MAX_BRACKET_SEARCH_LINES).find_map(|(line_index, line)| {
+                let end = if line_index == at.line_index { at.grapheme_index.saturating_add(1) } else { line.grapheme_count() };
+                (0..end)
+                    .rev()
+                    .find(|&grapheme_index| is_match(line, grapheme_index))
+                    .map(|grapheme_index| Location { grapheme_index, line_index })
+            })
         }
     }
 
+    ///根据文件的扩展名判断的文件类型
+    pub fn file_type(&self) -> FileType {
+        FileType::from_path(self.file_info.get_path())
+    }
+
     ///delete,删除光标后面的一位字符
     pub fn delete(&mut self, at: Location) {
         if let Some(line) = self.lines.get(at.line_index) {
             //判断是不是最后一个
             if at.grapheme_index >= line.grapheme_count() 
                 && at.line_index.saturating_add(1) < self.lines.len() {
-                    let next_line = self.lines.remove(at.line_index.saturating_add(1));
-                    self.lines[at.line_index].append(&next_line);
-                    self.dirty = true;
+                    self.join_with_next_line(at.line_index);
+                    self.mark_modified();
             } else if at.grapheme_index < line.grapheme_count() {//不是最后一个，直接删除光标所在位置的字符
                 self.lines[at.line_index].delete(at.grapheme_index);
-                self.dirty = true;
+                self.mark_modified();
             }
         }
     }
@@ -110,10 +681,10 @@ impl Buffer {
                     grapheme_index: previous_index
                 };
                self.delete(previous_positon);
-               self.dirty = true;
+               self.mark_modified();
             } else {//正常情况下，删除
                 line.delete(at.grapheme_index.saturating_sub(1));
-                self.dirty = true;
+                self.mark_modified();
             }
         }
     }
@@ -122,8 +693,9 @@ impl Buffer {
     pub fn tab(&mut self,at:Location) {
         if let Some(line) = self.lines.get_mut(at.line_index) {
             let new_line = line.split_off(at.grapheme_index);
-            self.lines.insert(at.line_index.saturating_add(1), new_line);
-            self.dirty = true
+            let ending = self.line_endings[at.line_index];
+            self.insert_line(at.line_index.saturating_add(1), new_line, ending);
+            self.mark_modified();
         }
     }
 
@@ -141,31 +713,416 @@ impl Buffer {
     //     Ok(())
     // }
 
-    ///将信息保存到指定的文件FileInfo
-    pub fn save_to_file(&self, file_info:&FileInfo) -> Result<(),Error>{
-        if let Some(file_path) = &file_info.get_path(){
-            let mut file = File::create(file_path)?;
-            for line in &self.lines {
-                writeln!(file,"{line}")?;
+    ///按照记录的换行符拼接所有行，最后一行是否换行与读取时保持一致
+    fn contents(&self) -> String {
+        let mut contents = String::new();
+        let last_index = self.height().saturating_sub(1);
+        for (index, (line, ending)) in self.lines.iter().zip(&self.line_endings).enumerate() {
+            contents.push_str(&line.to_string());
+            if index < last_index || !self.no_final_newline {
+                contents.push_str(ending.as_str());
             }
         }
+        contents
+    }
+
+    ///所有行的文本，不含换行符
+    pub fn text_lines(&self) -> Vec<String> {
+        self.lines.iter().map(Line::to_string).collect()
+    }
 
+    ///以指定的编码读取另一个文件的所有行，如备份文件、交换文件
+    pub fn read_lines(path: &Path, encoding: FileEncoding) -> Result<Vec<String>,Error> {
+        let bytes = fs::read(path)?;
+        let (contents, _) = encoding.decode(&bytes);
+        Ok(contents.split_inclusive('\n').map(|line| LineEnding::split(line).0.to_string()).collect())
+    }
+
+    ///用另一个文件(如备份文件、交换文件)的内容替换当前内容，视为一次修改，需要保存后才写入磁盘
+    pub fn restore_from(&mut self, path: &Path, encoding: FileEncoding) -> Result<(),Error> {
+        let bytes = fs::read(path)?;
+        let (contents, lossy) = encoding.decode(&bytes);
+        self.set_contents(&contents);
+        if lossy {
+            self.file_info.set_lossy(true);
+            self.options.read_only = true;
+        }
+        self.mark_modified();
         Ok(())
     }
 
-    ///将信息保存到指定的文件地址,并更新buffer指向的文件
-    pub fn save_as(&mut self, file_name: &str) -> Result<(),Error> {
-        let file_info = FileInfo::from(file_name); 
-        self.save_to_file(&file_info)?;
-        self.file_info = file_info;
+    ///只读的临时内容，如比较结果，没有对应的文件
+    pub fn scratch(title: &str, lines: &[String]) -> Self {
+        let mut buffer = Self {
+            file_info: FileInfo::scratch(title),
+            ..Self::default()
+        };
+        for line in lines {
+            buffer.lines.push(Line::from(line));
+            buffer.line_endings.push(LineEnding::default());
+        }
+        buffer
+    }
+
+    ///显示搜索结果的只读buffer，结果在搜索的过程中逐行加入
+    pub fn search_results(title: &str) -> Self {
+        Self {
+            file_info: FileInfo::scratch(title),
+            kind: BufferKind::SearchResults,
+            options: BufferOptions { read_only: true, ..BufferOptions::default() },
+            ..Self::default()
+        }
+    }
+
+    ///在搜索结果的末尾加入一处匹配
+    pub fn append_result(&mut self, text: &str, path: PathBuf, location: Location) {
+        self.lines.push(Line::from(text));
+        self.line_endings.push(LineEnding::default());
+        self.targets.push((path, location));
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    ///搜索结果中第 `line_index` 行对应的文件和位置
+    pub fn result_target(&self, line_index: usize) -> Option<(PathBuf, Location)> {
+        self.targets.get(line_index).cloned()
+    }
+
+    ///将信息保存到指定的文件FileInfo，并记录写入后文件的状态
+    /// 给出 settings 时覆盖之前按照配置备份原文件；自动保存不备份，否则备份很快就会轮换掉本次编辑之前的内容
+    fn save_to_file(&self, file_info:&mut FileInfo, backup_settings: Option<&Settings>) -> Result<(),Error>{
+        if let Some(file_path) = file_info.get_path(){
+            //先编码，无法编码时不会破坏原文件
+            let bytes = file_info.get_encoding().encode(&self.contents())?;
+            if let Some(settings) = backup_settings {
+                backup::backup(file_path, settings)?;
+            }
+            atomicwrite::write(file_path, &bytes)?;
+            file_info.record_disk_state(&bytes);
+        }
+
+        Ok(())
+    }
+
+    ///将信息保存到指定的文件地址,并更新buffer指向的文件；保存前按照新文件的类型整理内容
+    pub fn save_as(&mut self, file_path: &Path, settings: &Settings) -> Result<(),Error> {
+        self.save_with_hooks(FileType::from_path(Some(file_path)), settings, |buffer| {
+            let mut file_info = FileInfo::from_path(file_path); 
+            file_info.set_encoding(buffer.file_info.get_encoding());
+            buffer.save_to_file(&mut file_info, Some(settings))?;
+            buffer.file_info = file_info;
+            Ok(())
+        })?;
         self.dirty = false;
+        self.is_new_file = false;
+        self.options.read_only = false; //已经成功写入新的文件
+        self.options.auto_pairs = settings.autopairs.get(self.file_type()); //扩展名可能改变了文件类型
         Ok(()) 
     }
 
-    ///将信息保存到当前的文件
-    pub fn save(&mut self) -> Result<(),Error> {
-        self.save_to_file(&self.file_info)?;
+    ///将信息保存到当前的文件，保存前按照文件类型整理内容
+    pub fn save(&mut self, settings: &Settings) -> Result<(),Error> {
+        self.save_with_hooks(self.file_type(), settings, |buffer| buffer.save_to_current_file(Some(settings)))
+    }
+
+    ///自动保存：不整理内容，避免删除正在输入的空格，也不备份原文件
+    pub fn autosave(&mut self) -> Result<(),Error> {
+        self.save_to_current_file(None)
+    }
+
+    fn save_to_current_file(&mut self, backup_settings: Option<&Settings>) -> Result<(),Error> {
+        let mut file_info = self.file_info.clone();
+        self.save_to_file(&mut file_info, backup_settings)?;
+        file_info.set_lossy(false); //磁盘上已经是当前的内容
+        self.file_info = file_info;
         self.dirty = false;
+        self.is_new_file = false;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::settings::BackupMode;
+    use crate::editor::testdir::TestDir;
+
+    ///由文本内容构造的buffer
+    fn buffer_from(contents: &str) -> Buffer {
+        let mut buffer = Buffer::default();
+        buffer.set_contents(contents);
+        buffer
+    }
+
+    #[test]
+    fn contents_preserve_line_endings_and_missing_final_newline() {
+        for contents in ["a\r\nb\r\n", "a\nb\r\nc", "a\n\n", "", "only"] {
+            assert_eq!(buffer_from(contents).contents(), contents);
+        }
+    }
+
+    #[test]
+    fn inserted_lines_use_the_majority_line_ending() {
+        let mut buffer = buffer_from("a\r\nb\r\nc\n");
+        buffer.insert_newline(Location { grapheme_index: 1, line_index: 0 });
+        assert_eq!(buffer.contents(), "a\r\n\r\nb\r\nc\n");
+        assert_eq!(buffer.line_ending_style(), LineEndingStyle::Mixed);
+        buffer.convert_line_endings(LineEnding::Lf);
+        assert_eq!(buffer.contents(), "a\n\nb\nc\n");
+    }
+
+    ///在 (行, 字素) 处换行，返回换行后的内容和光标所在的字素位置
+    fn split_at(contents: &str, line_index: usize, grapheme_index: usize) -> (String, usize) {
+        let mut buffer = buffer_from(contents);
+        let column = buffer.insert_newline(Location { grapheme_index, line_index });
+        (buffer.contents(), column)
+    }
+
+    #[test]
+    fn newline_keeps_the_indentation_of_the_line() {
+        assert_eq!(split_at("    foo bar\n", 0, 8), ("    foo \n    bar\n".to_string(), 4));
+        assert_eq!(split_at("\tfoo\n", 0, 4), ("\tfoo\n\t\n".to_string(), 1));
+        assert_eq!(split_at("    \n", 0, 4), ("\n    \n".to_string(), 4));
+    }
+
+    #[test]
+    fn newline_inside_leading_whitespace_does_not_double_the_indent() {
+        assert_eq!(split_at("    foo\n", 0, 4), ("    \n    foo\n".to_string(), 4));
+        assert_eq!(split_at("    foo\n", 0, 2), ("  \n  foo\n".to_string(), 0));
+        assert_eq!(split_at("\t\tfoo\n", 0, 1), ("\t\n\tfoo\n".to_string(), 0));
+    }
+
+    #[test]
+    fn retab_uses_the_detected_or_configured_width() {
+        let mut buffer = buffer_from("a\n\tb\n\t\tc\n");
+        assert!(buffer.retab(true, 2));
+        assert_eq!(buffer.contents(), "a\n    b\n        c\n");
+        assert_eq!(buffer.options.indentation, Indentation::Spaces(4));
+        assert!(buffer.retab(false, 2));
+        assert_eq!(buffer.contents(), "a\n\tb\n\t\tc\n");
+        assert_eq!(buffer.options.indentation, Indentation::Tabs);
+
+        let mut buffer = buffer_from("a\nb\n");
+        assert!(!buffer.retab(true, 2));
+        assert_eq!(buffer.options.indentation, Indentation::Spaces(2));
+    }
+
+    #[test]
+    fn indent_lines_adds_one_level_and_skips_empty_lines() {
+        let mut buffer = buffer_from("a\n\n  中文\n");
+        buffer.options.indentation = Indentation::Spaces(4);
+        assert_eq!(buffer.indent_lines(0..=2), vec![4, 0, 4]);
+        assert_eq!(buffer.contents(), "    a\n\n      中文\n");
+
+        let mut buffer = buffer_from("a\nb\n");
+        buffer.options.indentation = Indentation::Tabs;
+        assert_eq!(buffer.indent_lines(1..=1), vec![1]);
+        assert_eq!(buffer.contents(), "a\n\tb\n");
+        assert!(buffer.dirty);
+    }
+
+    #[test]
+    fn dedent_lines_removes_at_most_one_level() {
+        let mut buffer = buffer_from("      a\n  b\n\t\tc\nd\n  \t中\n");
+        buffer.options.indentation = Indentation::Spaces(4);
+        assert_eq!(buffer.dedent_lines(0..=4), vec![4, 2, 1, 0, 2]);
+        assert_eq!(buffer.contents(), "  a\nb\n\tc\nd\n\t中\n");
+    }
+
+    #[test]
+    fn dedent_without_indentation_leaves_the_buffer_clean() {
+        let mut buffer = buffer_from("a\nb\n");
+        assert_eq!(buffer.dedent_lines(0..=5), vec![0; 6]);
+        assert!(!buffer.dirty);
+    }
+
+    #[test]
+    fn toggle_comment_round_trips_unicode_and_mixed_indentation() {
+        let contents = "\u{3000}a\n b\n\n\t  中文 c\n";
+        let mut buffer = buffer_from(contents);
+        let (comment, changes) = buffer.toggle_comment(0..=3, ("//", ""));
+        assert!(comment);
+        assert_eq!(changes, vec![Some((0, 3)), Some((0, 3)), None, Some((0, 3))]);
+        assert_eq!(buffer.contents(), "// \u{3000}a\n//  b\n\n// \t  中文 c\n");
+
+        let (comment, changes) = buffer.toggle_comment(0..=3, ("//", ""));
+        assert!(!comment);
+        assert_eq!(changes, vec![Some((0, 3)), Some((0, 3)), None, Some((0, 3))]);
+        assert_eq!(buffer.contents(), contents);
+    }
+
+    #[test]
+    fn toggle_comment_inserts_at_the_smallest_indent() {
+        let contents = "\t  é {\n\t    ü\n\t  }\n";
+        let mut buffer = buffer_from(contents);
+        let (comment, changes) = buffer.toggle_comment(0..=2, ("<!--", "-->"));
+        assert!(comment);
+        assert_eq!(changes, vec![Some((3, 5)); 3]);
+        assert_eq!(buffer.contents(), "\t  <!-- é { -->\n\t  <!--   ü -->\n\t  <!-- } -->\n");
+
+        let (comment, changes) = buffer.toggle_comment(0..=2, ("<!--", "-->"));
+        assert!(!comment);
+        assert_eq!(changes, vec![Some((3, 5)); 3]);
+        assert_eq!(buffer.contents(), contents);
+    }
+
+    #[test]
+    fn toggle_comment_comments_when_any_line_is_uncommented() {
+        let mut buffer = buffer_from("# a\nb\n");
+        let (comment, _) = buffer.toggle_comment(0..=1, ("#", ""));
+        assert!(comment);
+        assert_eq!(buffer.contents(), "# # a\n# b\n");
+    }
+
+    #[test]
+    fn save_as_recomputes_auto_pairs_for_the_new_file_type() {
+        let directory = TestDir::new("buffer-autopairs");
+        let mut settings = Settings { backup: BackupMode::Off, ..Settings::default() };
+        settings.set("autopairs.markdown", "off").unwrap();
+
+        let mut buffer = buffer_from("text\n");
+        buffer.options.auto_pairs = true;
+        buffer.save_as(&directory.join("notes.md"), &settings).unwrap();
+        assert!(!buffer.options.auto_pairs);
+        buffer.save_as(&directory.join("main.rs"), &settings).unwrap();
+        assert!(buffer.options.auto_pairs);
+    }
+
+    ///(行, 字素) 处的括号配对的括号的位置
+    fn bracket_match(buffer: &Buffer, line_index: usize, grapheme_index: usize) -> Option<(usize, usize)> {
+        buffer
+            .matching_bracket(Location { grapheme_index, line_index })
+            .map(|location| (location.line_index, location.grapheme_index))
+    }
+
+    #[test]
+    fn matching_bracket_skips_nested_pairs_in_both_directions() {
+        let buffer = buffer_from("fn f(a: [u8; (1)]) {\n    g(中(x));\n}\n");
+        assert_eq!(bracket_match(&buffer, 0, 4), Some((0, 17)));
+        assert_eq!(bracket_match(&buffer, 0, 17), Some((0, 4)));
+        assert_eq!(bracket_match(&buffer, 0, 8), Some((0, 16)));
+        assert_eq!(bracket_match(&buffer, 0, 19), Some((2, 0)));
+        assert_eq!(bracket_match(&buffer, 2, 0), Some((0, 19)));
+        assert_eq!(bracket_match(&buffer, 1, 5), Some((1, 10)));
+        assert_eq!(bracket_match(&buffer, 1, 7), Some((1, 9)));
+    }
+
+    #[test]
+    fn matching_bracket_needs_a_bracket_with_a_partner() {
+        let buffer = buffer_from("a(b\n)]\n");
+        assert_eq!(bracket_match(&buffer, 0, 0), None);
+        assert_eq!(bracket_match(&buffer, 1, 1), None);
+        assert_eq!(bracket_match(&buffer, 5, 0), None);
+        assert_eq!(bracket_match(&buffer, 0, 9), None);
+        assert_eq!(bracket_match(&buffer, 0, 1), Some((1, 0)));
+    }
+
+    #[test]
+    fn matching_bracket_gives_up_after_the_line_limit() {
+        let mut contents = String::from("(\n");
+        contents.push_str(&"x\n".repeat(MAX_BRACKET_SEARCH_LINES));
+        contents.push_str(")\n");
+        let buffer = buffer_from(&contents);
+        assert_eq!(bracket_match(&buffer, 0, 0), None);
+    }
+
+    #[test]
+    fn save_hooks_tidy_the_contents_and_can_be_undone() {
+        let contents = "a  \n\tb\t\n\n  \nc";
+        let mut buffer = buffer_from(contents);
+        let mut settings = Settings::default();
+        settings.set("trim_trailing_blank_lines", "on").unwrap();
+        buffer.apply_save_hooks(FileType::Text, &settings);
+        assert_eq!(buffer.contents(), "a\n\tb\n\n\nc\n");
+        assert!(buffer.undo_save_hooks());
+        assert_eq!(buffer.contents(), contents);
+        assert!(buffer.dirty);
+        assert!(!buffer.undo_save_hooks());
+
+        let mut buffer = buffer_from("a\n\n \n");
+        buffer.apply_save_hooks(FileType::Text, &settings);
+        assert_eq!(buffer.contents(), "a\n");
+    }
+
+    #[test]
+    fn save_hooks_follow_the_file_type_settings() {
+        let mut buffer = buffer_from("line break  \nnext");
+        buffer.apply_save_hooks(FileType::Markdown, &Settings::default());
+        assert_eq!(buffer.contents(), "line break  \nnext\n");
+
+        let mut settings = Settings::default();
+        settings.set("final_newline", "off").unwrap();
+        let mut buffer = buffer_from("a\nb");
+        buffer.apply_save_hooks(FileType::Rust, &settings);
+        assert_eq!(buffer.contents(), "a\nb");
+    }
+
+    #[test]
+    fn failed_save_leaves_the_buffer_untouched() {
+        let directory = TestDir::new("buffer-failed-save");
+        let contents = "a  \nb";
+        let mut buffer = buffer_from(contents);
+        buffer.dirty = true;
+        let settings = Settings::default();
+
+        assert!(buffer.save_as(&directory.join("missing/file.txt"), &settings).is_err());
+        assert_eq!(buffer.contents(), contents);
+        assert!(buffer.undo_snapshot.is_none());
+        assert!(buffer.dirty);
+        assert!(buffer.file_info.get_path().is_none());
+
+        buffer.save_as(&directory.join("file.txt"), &settings).unwrap();
+        assert_eq!(buffer.contents(), "a\nb\n");
+        assert!(buffer.undo_snapshot.is_some());
+    }
+
+    #[test]
+    fn save_hooks_without_changes_keep_no_snapshot() {
+        let mut buffer = buffer_from("tidy\n");
+        let revision = buffer.revision();
+        buffer.apply_save_hooks(FileType::Text, &Settings::default());
+        assert!(buffer.undo_snapshot.is_none());
+        assert_eq!(buffer.revision(), revision);
+        assert!(!buffer.undo_save_hooks());
+    }
+
+    #[test]
+    fn edits_after_saving_discard_the_snapshot() {
+        let mut buffer = buffer_from("a \n");
+        buffer.apply_save_hooks(FileType::Text, &Settings::default());
+        buffer.insert_char('x', Location { grapheme_index: 0, line_index: 0 });
+        assert!(!buffer.undo_save_hooks());
+        assert_eq!(buffer.contents(), "xa\n");
+    }
+
+    #[test]
+    fn undecodable_files_open_read_only_until_reopened_correctly() {
+        let directory = TestDir::new("buffer-lossy");
+        let path = directory.join("latin.txt");
+        fs::write(&path, b"caf\xe9\n").unwrap();
+        let settings = Settings::default();
+
+        let mut buffer = Buffer::load(&path.to_string_lossy(), &settings).unwrap();
+        assert!(!buffer.file_info.is_lossy());
+        assert!(!buffer.options.read_only);
+
+        buffer.reload_with_encoding(FileEncoding::default()).unwrap();
+        assert!(buffer.file_info.is_lossy());
+        assert!(buffer.options.read_only);
+        assert_eq!(buffer.contents(), "caf\u{FFFD}\n");
+
+        buffer.reload_with_encoding(FileEncoding::from_label("latin1").unwrap()).unwrap();
+        assert!(!buffer.file_info.is_lossy());
+        assert!(!buffer.options.read_only);
+        assert_eq!(buffer.contents(), "café\n");
+    }
+
+    #[test]
+    fn loading_undecodable_bytes_opens_read_only() {
+        let directory = TestDir::new("buffer-lossy-load");
+        let path = directory.join("wide.txt");
+        fs::write(&path, [0xFF, 0xFE, b'a', 0, 0x00, 0xD8]).unwrap();
+        let buffer = Buffer::load(&path.to_string_lossy(), &Settings::default()).unwrap();
+        assert!(buffer.file_info.is_lossy());
+        assert!(buffer.options.read_only);
+    }
+}
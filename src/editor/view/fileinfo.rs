@@ -1,12 +1,54 @@
+use crate::editor::encoding::FileEncoding;
 use std::{
     fmt::{self,Display},
-    path::{Path,PathBuf,} //引入PathBuf结构体,用于表示文件路径,内部提供方法修改，是可变类型
+    fs,
+    hash::{DefaultHasher,Hash,Hasher},
+    path::{Path,PathBuf,}, //引入PathBuf结构体,用于表示文件路径,内部提供方法修改，是可变类型
+    time::SystemTime,
 };
 
+///读取或保存时文件在磁盘上的状态，用于发现其他程序对文件的修改
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl DiskState {
+    ///根据磁盘上的文件以及其内容记录状态
+    pub fn new(path: &Path, bytes: &[u8]) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: Self::hash(bytes),
+        })
+    }
+
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+///磁盘上的文件与记录的状态相比发生的变化
+#[derive(Clone,Copy,PartialEq,Eq)]
+pub enum DiskChange {
+    Unchanged,
+    Modified,
+    Removed,
+}
+
 ///存储文件地址的数据结构
 #[derive(Default,Debug,Clone)]
 pub struct FileInfo {
     path: Option<PathBuf>, //PathBuf 能够很好地对文件地址进行一些操作，比如push,pop,join,set_extension
+    encoding: FileEncoding, //读取时识别出的编码，保存时使用
+    title: Option<String>, //没有对应文件的临时内容显示的名称
+    disk_state: Option<DiskState>, //上一次读取或保存时文件的状态
+    lossy: bool, //读取时有无法按编码解码的字节被替换，保存会丢失原来的字节
 }
 
 impl FileInfo {
@@ -14,14 +56,121 @@ impl FileInfo {
     pub fn from(file_name: &str) -> Self { 
         Self {
             path: Some(PathBuf::from(file_name)),
+            ..Self::default()
         }       
     }
 
+    ///没有对应文件的临时内容，只显示名称
+    pub fn scratch(title: &str) -> Self {
+        Self {
+            title: Some(title.to_string()),
+            ..Self::default()
+        }
+    }
+
+    ///由已有的路径构造
+    pub fn from_path(path: &Path) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        }
+    }
+
+    ///记录文件在磁盘上的状态，bytes 为刚刚读取或写入的内容
+    pub fn record_disk_state(&mut self, bytes: &[u8]) {
+        self.disk_state = self.get_path().and_then(|path| DiskState::new(path, bytes));
+    }
+
+    ///与上一次记录的状态相比，磁盘上的文件是否被其他程序修改或删除
+    /// 修改时间和大小都没有变化时认为没有修改；否则比较内容的哈希，只是修改时间变化时更新记录
+    pub fn check_disk_state(&mut self) -> DiskChange {
+        let (Some(path), Some(state)) = (self.path.as_deref(), self.disk_state) else {
+            return DiskChange::Unchanged;
+        };
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return DiskChange::Removed,
+            Err(_) => return DiskChange::Unchanged,
+        };
+        if metadata.modified().ok() == state.modified && metadata.len() == state.len {
+            return DiskChange::Unchanged;
+        }
+        match fs::read(path) {
+            Ok(bytes) if DiskState::hash(&bytes) == state.hash => {
+                self.disk_state = DiskState::new(path, &bytes);
+                DiskChange::Unchanged
+            },
+            Ok(_) => DiskChange::Modified,
+            Err(_) => DiskChange::Unchanged,
+        }
+    }
+
+    ///用户知晓磁盘上的变化之后，以磁盘上的当前状态为准，不再重复提示
+    pub fn acknowledge_disk_state(&mut self) {
+        self.disk_state = self.get_path().and_then(|path| {
+            let bytes = fs::read(path).ok()?;
+            DiskState::new(path, &bytes)
+        });
+    }
+
+    ///文件的编码
+    pub const fn get_encoding(&self) -> FileEncoding {
+        self.encoding
+    }
+
+    ///设置文件的编码
+    pub fn set_encoding(&mut self, encoding: FileEncoding) {
+        self.encoding = encoding;
+    }
+
+    ///读取的内容是否有无法解码的字节
+    pub const fn is_lossy(&self) -> bool {
+        self.lossy
+    }
+
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    ///文件在磁盘上被移动(如重命名)之后，更新地址，保留编码等其他信息
+    pub fn set_path(&mut self, path: &Path) {
+        self.path = Some(path.to_path_buf());
+    }
+
     ///获取Option<&Path>
     pub fn get_path(&self) -> Option<&Path> {
         self.path.as_deref()
     }
 
+    ///文件所在的目录，没有地址或者地址中没有目录部分时返回None
+    pub fn get_directory(&self) -> Option<&Path> {
+        self.get_path()
+            .and_then(Path::parent)
+            .filter(|dir| !dir.as_os_str().is_empty())
+    }
+
+    ///判断给定路径与当前地址是否指向同一个文件，两者都存在时比较规范化之后的路径
+    pub fn is_same_file(&self, other: &Path) -> bool {
+        let Some(path) = self.get_path() else {
+            return false;
+        };
+        match (fs::canonicalize(path), fs::canonicalize(other)) {
+            (Ok(path), Ok(other)) => path == other,
+            _ => path == other,
+        }
+    }
+
+    ///当前用户能否写入该文件，文件尚不存在时视为可写
+    pub fn is_writable(&self) -> bool {
+        let Some(path) = self.get_path() else {
+            return true;
+        };
+        match fs::OpenOptions::new().write(true).open(path) {
+            Ok(_) => true,
+            Err(err) => err.kind() != std::io::ErrorKind::PermissionDenied,
+        }
+    }
+
     ///判断是否有地址
     pub const fn has_path(&self) -> bool {
         self.path.is_some()
@@ -31,10 +180,11 @@ impl FileInfo {
 
 impl Display for FileInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        //如果path不为空，则会返回路径;如果为空，则会返回"[No name]"
+        //如果path不为空，则会返回路径;如果为空，则会返回名称或者"[No name]"
         let name = self.get_path()
             .and_then(|path| path.file_name())
             .and_then(|name| name.to_str())
+            .or(self.title.as_deref())
             .unwrap_or("[No Name]");
 
         write!(f,"{name}")     
@@ -0,0 +1,52 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SWAP_SUFFIX: &str = ".hecto-swp";
+
+///buffer 与交换文件相关的状态
+#[derive(Default)]
+pub struct SwapState {
+    pub(super) written_revision: Option<u64>, //写入交换文件时的revision，None表示没有写过交换文件
+    pub suspended: bool, //保留了上一次留下的交换文件时，不再写入交换文件
+    pub checked: bool,   //是否已经检查过上一次异常退出留下的交换文件
+}
+
+///文件对应的交换文件，与文件位于同一目录，如 `dir/.file.hecto-swp`
+pub fn swap_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{file_name}{SWAP_SUFFIX}"))
+}
+
+///文件名是否是编辑器自己的交换文件，目录列表中不显示
+pub fn is_swap_file(name: &str) -> bool {
+    name.starts_with('.') && name.ends_with(SWAP_SUFFIX)
+}
+
+///存在比文件更新的交换文件时返回其路径，说明上一次编辑没有正常结束
+pub fn pending_recovery(path: &Path) -> Option<PathBuf> {
+    let swap = swap_path(path);
+    let swap_modified = fs::metadata(&swap).and_then(|metadata| metadata.modified()).ok()?;
+    match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(file_modified) if file_modified >= swap_modified => None,
+        _ => Some(swap),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_files_are_recognised_by_name() {
+        let swap = swap_path(Path::new("dir/notes.txt"));
+        assert_eq!(swap, Path::new("dir/.notes.txt.hecto-swp"));
+        assert!(is_swap_file(&swap.file_name().unwrap().to_string_lossy()));
+        assert!(!is_swap_file("notes.txt"));
+        assert!(!is_swap_file("notes.hecto-swp"));
+    }
+}